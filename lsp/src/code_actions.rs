@@ -0,0 +1,314 @@
+use std::collections::HashMap;
+use tower_lsp::lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, Command, Diagnostic, Position, Range,
+    TextEdit, Url, WorkspaceEdit,
+};
+
+use crate::diagnostics::{Fix, SOURCE};
+use crate::parse::{parse_assignment, utf16_col, Assignment};
+use crate::schema::GhosttySchema;
+
+/// Builds quick fixes for the server's own diagnostics in the requested range.
+pub fn quick_fixes(uri: &Url, diagnostics: &[Diagnostic]) -> Vec<CodeActionOrCommand> {
+    diagnostics
+        .iter()
+        .filter(|d| d.source.as_deref() == Some(SOURCE))
+        .filter_map(|d| {
+            let fix = Fix::from_diagnostic(d)?;
+            Some(quick_fix(&fix.title, uri, d, fix.edit))
+        })
+        .collect()
+}
+
+/// Offered on an empty document: fills it with the starter config.
+pub fn insert_template(uri: &Url, template: String) -> CodeActionOrCommand {
+    CodeActionOrCommand::CodeAction(CodeAction {
+        title: "Insert starter config".to_string(),
+        kind: Some(CodeActionKind::SOURCE),
+        edit: Some(template_edit(uri, template)),
+        ..Default::default()
+    })
+}
+
+/// The known option assigned on `line`, if any.
+pub fn key_on_line<'a>(schema: &GhosttySchema, text: &'a str, line: u32) -> Option<&'a str> {
+    let assignment = parse_assignment(text.lines().nth(line as usize)?)?;
+    schema
+        .options
+        .contains_key(assignment.key)
+        .then_some(assignment.key)
+}
+
+/// Offered on a line that sets a known option: runs `command` to open the
+/// option's documentation.
+pub fn open_docs(key: &str, command: &str) -> CodeActionOrCommand {
+    let title = format!("Open documentation for `{}`", key);
+    CodeActionOrCommand::CodeAction(CodeAction {
+        title: title.clone(),
+        command: Some(Command::new(
+            title,
+            command.to_string(),
+            Some(vec![key.into()]),
+        )),
+        ..Default::default()
+    })
+}
+
+/// Inserts `template` at the start of the document.
+pub fn template_edit(uri: &Url, template: String) -> WorkspaceEdit {
+    let edit = TextEdit::new(Range::default(), template);
+    WorkspaceEdit {
+        changes: Some(HashMap::from([(uri.clone(), vec![edit])])),
+        ..Default::default()
+    }
+}
+
+/// Flips the boolean assignment on `line` between `true` and `false`. An
+/// empty or unrecognised value becomes the opposite of the option's default,
+/// or `true` when the schema gives none. `None` when the line doesn't
+/// set a boolean option, or sets a union such as `boolean|number` to a
+/// non-boolean value.
+pub fn toggle_boolean(
+    schema: &GhosttySchema,
+    uri: &Url,
+    text: &str,
+    line: u32,
+) -> Option<WorkspaceEdit> {
+    let line_text = text.lines().nth(line as usize)?;
+    let assignment = parse_assignment(line_text)?;
+    let opt = schema.options.get(assignment.key)?;
+    if !opt.has_type("boolean") {
+        return None;
+    }
+
+    let from_default = match opt.default.as_deref() {
+        Some("true") => "false",
+        _ => "true",
+    };
+    // `yes`/`on` and `no`/`off` aren't valid, but their intent is clear
+    let toggled = match assignment.value.to_ascii_lowercase().as_str() {
+        "true" | "1" | "t" | "yes" | "on" => "false",
+        "false" | "0" | "f" | "no" | "off" => "true",
+        "" => from_default,
+        _ if opt.option_type != "boolean" => return None,
+        _ => from_default,
+    };
+    let spaced = assignment.value.is_empty() && !line_text.ends_with(char::is_whitespace);
+    let new_text = if spaced {
+        format!(" {}", toggled)
+    } else {
+        toggled.to_string()
+    };
+    Some(value_edit(uri, line, line_text, &assignment, new_text))
+}
+
+/// Converts the `theme` on `line` between a single theme and the
+/// `light:...,dark:...` pair Ghostty switches between with the system
+/// appearance. A single theme starts out on both sides; a pair collapses to
+/// its dark theme, or its light one with `keep_light` or when it has no dark
+/// side. `None` when the line doesn't set `theme` or the value is neither.
+pub fn toggle_theme_mode(
+    uri: &Url,
+    text: &str,
+    line: u32,
+    keep_light: bool,
+) -> Option<WorkspaceEdit> {
+    let line_text = text.lines().nth(line as usize)?;
+    let assignment = parse_assignment(line_text)?.unquoted();
+    if assignment.key != "theme" || assignment.value.is_empty() {
+        return None;
+    }
+
+    let value = assignment.value;
+    let is_pair = value.split(',').any(|part| {
+        let part = part.trim_start();
+        part.starts_with("light:") || part.starts_with("dark:")
+    });
+    let new_text = if is_pair {
+        let (light, dark) = theme_pair(value)?;
+        let kept = if keep_light {
+            light.or(dark)
+        } else {
+            dark.or(light)
+        };
+        kept?.to_string()
+    } else if value.contains(',') {
+        return None;
+    } else {
+        format!("light:{},dark:{}", value, value)
+    };
+    Some(value_edit(uri, line, line_text, &assignment, new_text))
+}
+
+/// The light and dark themes of a `light:...,dark:...` value. `None` when a
+/// part isn't `light:` or `dark:` followed by a name.
+fn theme_pair(value: &str) -> Option<(Option<&str>, Option<&str>)> {
+    let (mut light, mut dark) = (None, None);
+    for part in value.split(',') {
+        let (side, name) = part.trim().split_once(':')?;
+        let name = name.trim();
+        if name.is_empty() {
+            return None;
+        }
+        match side.trim() {
+            "light" => light = Some(name),
+            "dark" => dark = Some(name),
+            _ => return None,
+        }
+    }
+    Some((light, dark))
+}
+
+/// Replaces the value of `assignment` on `line`.
+fn value_edit(
+    uri: &Url,
+    line: u32,
+    line_text: &str,
+    assignment: &Assignment,
+    new_text: String,
+) -> WorkspaceEdit {
+    let range = Range::new(
+        Position::new(line, utf16_col(line_text, assignment.value_start)),
+        Position::new(
+            line,
+            utf16_col(line_text, assignment.value_start + assignment.value.len()),
+        ),
+    );
+    WorkspaceEdit {
+        changes: Some(HashMap::from([(
+            uri.clone(),
+            vec![TextEdit::new(range, new_text)],
+        )])),
+        ..Default::default()
+    }
+}
+
+fn quick_fix(
+    title: &str,
+    uri: &Url,
+    diagnostic: &Diagnostic,
+    edit: TextEdit,
+) -> CodeActionOrCommand {
+    CodeActionOrCommand::CodeAction(CodeAction {
+        title: title.to_string(),
+        kind: Some(CodeActionKind::QUICKFIX),
+        diagnostics: Some(vec![diagnostic.clone()]),
+        edit: Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(uri.clone(), vec![edit])])),
+            ..Default::default()
+        }),
+        is_preferred: Some(true),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uri() -> Url {
+        Url::parse("file:///tmp/ghostty/config").unwrap()
+    }
+
+    /// The one-line `text` after applying `edit`.
+    fn applied(text: &str, edit: Option<WorkspaceEdit>) -> Option<String> {
+        let mut changes = edit?.changes?;
+        let edits = changes.remove(&uri())?;
+        let [edit] = &edits[..] else {
+            panic!("expected a single edit, got {:?}", edits);
+        };
+        let start = crate::parse::byte_offset(text, edit.range.start.character);
+        let end = crate::parse::byte_offset(text, edit.range.end.character);
+        Some(format!(
+            "{}{}{}",
+            &text[..start],
+            edit.new_text,
+            &text[end..]
+        ))
+    }
+
+    fn toggled(text: &str) -> Option<String> {
+        applied(
+            text,
+            toggle_boolean(&GhosttySchema::load(), &uri(), text, 0),
+        )
+    }
+
+    #[test]
+    fn toggles_a_boolean_value() {
+        assert_eq!(
+            toggled("maximize = true").as_deref(),
+            Some("maximize = false")
+        );
+        assert_eq!(
+            toggled("maximize = false").as_deref(),
+            Some("maximize = true")
+        );
+        assert_eq!(
+            toggled("maximize = yes").as_deref(),
+            Some("maximize = false")
+        );
+        assert_eq!(toggled("maximize =").as_deref(), Some("maximize = true"));
+    }
+
+    #[test]
+    fn unrecognised_value_becomes_the_opposite_of_the_default() {
+        // `maximize` is off by default, `link-url` on
+        assert_eq!(
+            toggled("maximize = maybe").as_deref(),
+            Some("maximize = true")
+        );
+        assert_eq!(
+            toggled("link-url = maybe").as_deref(),
+            Some("link-url = false")
+        );
+        assert_eq!(toggled("link-url =").as_deref(), Some("link-url = false"));
+        // Without a single default, `true`
+        assert_eq!(
+            toggled("cursor-style-blink = maybe").as_deref(),
+            Some("cursor-style-blink = true")
+        );
+    }
+
+    #[test]
+    fn only_boolean_options_toggle() {
+        assert_eq!(toggled("font-size = 12"), None);
+        assert_eq!(toggled("# maximize = true"), None);
+        // A union set to its other member isn't a boolean right now
+        assert_eq!(toggled("background-blur = 20"), None);
+        assert_eq!(
+            toggled("background-blur = true").as_deref(),
+            Some("background-blur = false")
+        );
+    }
+
+    #[test]
+    fn single_theme_becomes_a_light_dark_pair() {
+        let text = "theme = Dracula";
+        assert_eq!(
+            applied(text, toggle_theme_mode(&uri(), text, 0, false)).as_deref(),
+            Some("theme = light:Dracula,dark:Dracula")
+        );
+        let text = "theme = \"Builtin Dark\"";
+        assert_eq!(
+            applied(text, toggle_theme_mode(&uri(), text, 0, false)).as_deref(),
+            Some("theme = \"light:Builtin Dark,dark:Builtin Dark\"")
+        );
+    }
+
+    #[test]
+    fn theme_pair_collapses_to_one_side() {
+        let text = "theme = light:Solarized Light, dark:Nord";
+        let collapse = |keep_light| applied(text, toggle_theme_mode(&uri(), text, 0, keep_light));
+        assert_eq!(collapse(false).as_deref(), Some("theme = Nord"));
+        assert_eq!(collapse(true).as_deref(), Some("theme = Solarized Light"));
+
+        let text = "theme = light:Solarized Light";
+        assert_eq!(
+            applied(text, toggle_theme_mode(&uri(), text, 0, false)).as_deref(),
+            Some("theme = Solarized Light")
+        );
+        assert!(toggle_theme_mode(&uri(), "theme = a,b", 0, false).is_none());
+        assert!(toggle_theme_mode(&uri(), "font-family = Nord", 0, false).is_none());
+    }
+}
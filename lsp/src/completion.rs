@@ -0,0 +1,1714 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+use tower_lsp::lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionItemLabelDetails, CompletionItemTag,
+    CompletionTextEdit, Documentation, InsertTextFormat, MarkupContent, MarkupKind, Position,
+    Range, TextEdit,
+};
+
+use crate::config::{Config, EnumCompletionStyle};
+use crate::parse::{
+    byte_offset, flag_name, looks_like_text, split_keybind, strip_keybind_prefixes, utf16_col,
+    LineKind, ParsedDocument, ParsedLine,
+};
+use crate::paths;
+use crate::schema::{ConfigOption, GhosttySchema};
+use crate::themes;
+
+/// Common patterns offered when completing a `regex` value.
+const REGEX_SNIPPETS: &[(&str, &str)] = &[
+    ("URL matcher", r#"https?://[^\s"'<>]+"#),
+    ("File path matcher", r"(?:~|\.{1,2})?/[\w.\-/]+"),
+    ("Email address matcher", r"[\w.+-]+@[\w-]+\.[\w.-]+"),
+    ("Issue reference matcher", r"#\d+"),
+];
+
+/// Completions for `position` in `document`, unranked and untruncated.
+/// Relative paths are completed against `base_dir`.
+pub fn complete(
+    schema: &GhosttySchema,
+    config: &Config,
+    document: &ParsedDocument,
+    position: Position,
+    base_dir: Option<&Path>,
+) -> Vec<CompletionItem> {
+    if !looks_like_text(document.text()) {
+        return vec![];
+    }
+    let insert = KeyInsert::for_config(config);
+    let Some(line) = document.line(position.line as usize) else {
+        let mut items = get_key_completions(schema, "", None, insert);
+        items.extend(keybind_recipe_completions(
+            Range::new(position, position),
+            false,
+        ));
+        if config.deprioritize_set_keys {
+            let set_keys = keys_set_elsewhere(document, position.line);
+            deprioritize_set_keys(schema, &set_keys, &mut items);
+        }
+        sort_completions(&mut items);
+        return items;
+    };
+
+    let mut items = match parse_line_context(line, position.character) {
+        LineContext::Comment => vec![],
+        LineContext::Key {
+            partial,
+            start,
+            existing_end,
+            word_end,
+        } => {
+            let start = Position::new(position.line, start);
+            let mut items = match existing_end {
+                // The line already has its `=`, so only the key is replaced
+                Some(end) => {
+                    let end = Position::new(position.line, end.max(position.character));
+                    let range = Range::new(start, end);
+                    get_key_completions(schema, &partial, Some(range), KeyInsert::Key)
+                }
+                None => {
+                    let range = Range::new(start, Position::new(position.line, word_end));
+                    let mut items = get_key_completions(schema, &partial, Some(range), insert);
+                    if "keybind".starts_with(&partial.to_lowercase()) {
+                        items.extend(keybind_recipe_completions(range, false));
+                    }
+                    items
+                }
+            };
+            if config.deprioritize_set_keys {
+                let set_keys = keys_set_elsewhere(document, position.line);
+                deprioritize_set_keys(schema, &set_keys, &mut items);
+            }
+            items
+        }
+        LineContext::Value {
+            key,
+            partial,
+            start,
+        } => {
+            let range = Range::new(Position::new(position.line, start), position);
+            let mut items = get_value_completions(schema, config, &key, &partial, range, base_dir);
+            if key == "keybind" && partial.trim().is_empty() {
+                items.extend(keybind_recipe_completions(range, true));
+            }
+            if key == "palette" && !partial.contains('=') {
+                if let Some(next) = next_palette_entry(document, position.line) {
+                    for item in &mut items {
+                        item.preselect = None;
+                    }
+                    items.push(next);
+                }
+            }
+            items
+        }
+    };
+    sort_completions(&mut items);
+    items
+}
+
+/// A preselected entry for the lowest palette index no other line sets yet.
+fn next_palette_entry(document: &ParsedDocument, skip_line: u32) -> Option<CompletionItem> {
+    let used: HashSet<u8> = document
+        .assignments()
+        .filter(|(line_num, _, assignment)| {
+            *line_num != skip_line as usize && assignment.key == "palette"
+        })
+        .map(|(_, _, assignment)| assignment)
+        .filter_map(|assignment| assignment.value.split('=').next()?.trim().parse().ok())
+        .collect();
+    let index = (0..=u8::MAX).find(|i| !used.contains(i))?;
+
+    Some(CompletionItem {
+        label: format!("{}=", index),
+        kind: Some(CompletionItemKind::SNIPPET),
+        detail: Some("Next unused palette index".to_string()),
+        insert_text: Some(format!("{}=${{1:#000000}}", index)),
+        insert_text_format: Some(InsertTextFormat::SNIPPET),
+        preselect: Some(true),
+        sort_text: Some(format!("!{}", index)),
+        ..Default::default()
+    })
+}
+
+/// Orders items the way clients display them, by `sort_text` falling back to
+/// the label, so results don't depend on the schema's `HashMap` order.
+fn sort_completions(items: &mut [CompletionItem]) {
+    fn sort_key(item: &CompletionItem) -> &str {
+        item.sort_text.as_deref().unwrap_or(&item.label)
+    }
+    items.sort_by(|a, b| {
+        sort_key(a)
+            .cmp(sort_key(b))
+            .then_with(|| a.label.cmp(&b.label))
+    });
+}
+
+/// Keys assigned in `document` on any line but `skip_line`, the one being
+/// edited.
+fn keys_set_elsewhere(document: &ParsedDocument, skip_line: u32) -> HashSet<&str> {
+    document
+        .assignments()
+        .filter(|(line_num, _, _)| *line_num != skip_line as usize)
+        .map(|(_, _, assignment)| assignment.key)
+        .collect()
+}
+
+/// Sorts non-repeatable keys the document already sets after the rest and
+/// marks them, nudging towards options that aren't configured yet.
+fn deprioritize_set_keys(
+    schema: &GhosttySchema,
+    set_keys: &HashSet<&str>,
+    items: &mut [CompletionItem],
+) {
+    for item in items.iter_mut() {
+        let Some(opt) = schema.options.get(&item.label) else {
+            continue;
+        };
+        if opt.repeatable || !set_keys.contains(item.label.as_str()) {
+            continue;
+        }
+        item.sort_text = Some(format!("y_{}", item.label));
+        item.label_details = Some(CompletionItemLabelDetails {
+            detail: None,
+            description: Some("already set".to_string()),
+        });
+    }
+}
+
+/// Whether a lowercased `query` finds `key`: as a substring, or as the starts
+/// of its hyphen-separated words in order, so `wpx` and `padx` both find
+/// `window-padding-x`.
+fn key_matches(key: &str, query: &str) -> bool {
+    let key = key.to_lowercase();
+    if key.contains(query) {
+        return true;
+    }
+    let words: Vec<&str> = key.split('-').collect();
+    matches_word_starts(&words, query)
+}
+
+fn matches_word_starts(words: &[&str], query: &str) -> bool {
+    let Some((word, rest)) = words.split_first() else {
+        return query.is_empty();
+    };
+    if query.is_empty() {
+        return true;
+    }
+    // Each word either supplies a prefix of the query or is skipped
+    let shared = word
+        .bytes()
+        .zip(query.bytes())
+        .take_while(|(a, b)| a == b)
+        .count();
+    (1..=shared)
+        .rev()
+        .any(|len| matches_word_starts(rest, &query[len..]))
+        || matches_word_starts(rest, query)
+}
+
+/// The first letter of each hyphen-separated word: `wpx` for
+/// `window-padding-x`. Added to `filter_text` so clients' own matching finds
+/// keys by their initials too.
+fn initials(key: &str) -> String {
+    key.split('-')
+        .filter_map(|word| word.chars().next())
+        .collect()
+}
+
+/// What accepting a key completion inserts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyInsert {
+    /// Just the key, for a line that already has its `=`.
+    Key,
+    /// `key = `
+    Assignment,
+    /// `key = value` for enum keys, with the value as a snippet placeholder.
+    AssignmentWithValue,
+}
+
+impl KeyInsert {
+    pub fn for_config(config: &Config) -> Self {
+        if config.complete_key_with_value {
+            Self::AssignmentWithValue
+        } else {
+            Self::Assignment
+        }
+    }
+}
+
+/// `replace_range` covers the partial key already typed so accepting a
+/// completion replaces it instead of inserting after it.
+pub fn get_key_completions(
+    schema: &GhosttySchema,
+    partial: &str,
+    replace_range: Option<Range>,
+    insert: KeyInsert,
+) -> Vec<CompletionItem> {
+    let partial_lower = partial.to_lowercase();
+    let mut items: Vec<CompletionItem> = schema
+        .options
+        .iter()
+        .filter(|(key, _)| key_matches(key, &partial_lower))
+        .map(|(key, opt)| {
+            let detail = format_type_detail(opt);
+            let value = (insert == KeyInsert::AssignmentWithValue)
+                .then(|| default_enum_value(opt))
+                .flatten();
+            // Plain text is the protocol default, so only snippets say so
+            let (insert, format) = match (insert, value) {
+                (KeyInsert::Key, _) => (key.clone(), None),
+                (_, Some(value)) => (
+                    format!("{} = ${{1:{}}}", key, escape_snippet(value)),
+                    Some(InsertTextFormat::SNIPPET),
+                ),
+                (_, None) => (format!("{} = ", key), None),
+            };
+            let (insert_text, text_edit) = match replace_range {
+                Some(range) => (
+                    None,
+                    Some(CompletionTextEdit::Edit(TextEdit::new(range, insert))),
+                ),
+                None => (Some(insert), None),
+            };
+            let mut item = CompletionItem {
+                label: key.clone(),
+                kind: Some(key_completion_kind(opt)),
+                detail: Some(detail),
+                documentation: Some(Documentation::MarkupContent(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: format_key_documentation(key, opt),
+                })),
+                filter_text: key
+                    .contains('-')
+                    .then(|| format!("{} {}", key, initials(key))),
+                insert_text,
+                text_edit,
+                insert_text_format: format,
+                ..Default::default()
+            };
+            if opt.deprecated {
+                item.tags = Some(vec![CompletionItemTag::DEPRECATED]);
+                item.sort_text = Some(format!("z_{}", key));
+            }
+            item
+        })
+        .collect();
+
+    // Scaffolding the value only makes sense when the line has no `=` yet
+    if insert != KeyInsert::Key {
+        items.extend(
+            LINE_SNIPPETS
+                .iter()
+                .filter(|(key, _, _)| schema.options.contains_key(*key))
+                .filter(|(key, _, _)| key_matches(key, &partial_lower))
+                .map(|(key, snippet, detail)| {
+                    let (insert_text, text_edit) = match replace_range {
+                        Some(range) => (
+                            None,
+                            Some(CompletionTextEdit::Edit(TextEdit::new(
+                                range,
+                                snippet.to_string(),
+                            ))),
+                        ),
+                        None => (Some(snippet.to_string()), None),
+                    };
+                    CompletionItem {
+                        label: format!("{} = …", key),
+                        kind: Some(CompletionItemKind::SNIPPET),
+                        detail: Some(detail.to_string()),
+                        filter_text: Some(key.to_string()),
+                        insert_text,
+                        text_edit,
+                        insert_text_format: Some(InsertTextFormat::SNIPPET),
+                        ..Default::default()
+                    }
+                }),
+        );
+    }
+    sort_completions(&mut items);
+    items
+}
+
+/// Whole-line snippets offered alongside the plain key for repeatable keys
+/// whose values have a fixed shape.
+const LINE_SNIPPETS: &[(&str, &str, &str)] = &[
+    (
+        "keybind",
+        "keybind = ${1:ctrl+a}=${2:action}",
+        "Keybind line: trigger and action",
+    ),
+    (
+        "palette",
+        "palette = ${1:0}=${2:#000000}",
+        "Palette entry: index and colour",
+    ),
+];
+
+/// Sets of keybinds that work together, each a label, a description and the
+/// `keybind` values it adds. Offered on a line with no key yet or right after
+/// `keybind =`.
+const KEYBIND_RECIPES: &[(&str, &str, &[&str])] = &[
+    (
+        "tmux-style splits",
+        "Split and move between panes after a ctrl+a leader",
+        &[
+            "ctrl+a>backslash=new_split:right",
+            "ctrl+a>minus=new_split:down",
+            "ctrl+a>h=goto_split:left",
+            "ctrl+a>j=goto_split:down",
+            "ctrl+a>k=goto_split:up",
+            "ctrl+a>l=goto_split:right",
+            "ctrl+a>z=toggle_split_zoom",
+        ],
+    ),
+    (
+        "macOS tab navigation",
+        "Open, close and switch tabs with cmd",
+        &[
+            "super+t=new_tab",
+            "super+w=close_surface",
+            "super+shift+left_bracket=previous_tab",
+            "super+shift+right_bracket=next_tab",
+            "super+1=goto_tab:1",
+            "super+2=goto_tab:2",
+            "super+3=goto_tab:3",
+            "super+9=last_tab",
+        ],
+    ),
+    (
+        "vim-style pane movement",
+        "Move between splits with ctrl+h/j/k/l and resize them with ctrl+shift",
+        &[
+            "ctrl+h=goto_split:left",
+            "ctrl+j=goto_split:down",
+            "ctrl+k=goto_split:up",
+            "ctrl+l=goto_split:right",
+            "ctrl+shift+h=resize_split:left,10",
+            "ctrl+shift+j=resize_split:down,10",
+            "ctrl+shift+k=resize_split:up,10",
+            "ctrl+shift+l=resize_split:right,10",
+        ],
+    ),
+];
+
+/// One item per recipe, inserting a `keybind` line for each binding in place
+/// of `range`. With `after_key` the line already reads `keybind =`, so the
+/// first binding completes it.
+fn keybind_recipe_completions(range: Range, after_key: bool) -> Vec<CompletionItem> {
+    KEYBIND_RECIPES
+        .iter()
+        .map(|(label, description, bindings)| {
+            let lines: Vec<String> = bindings
+                .iter()
+                .enumerate()
+                .map(|(i, binding)| match i {
+                    0 if after_key => binding.to_string(),
+                    _ => format!("keybind = {}", binding),
+                })
+                .collect();
+            let text = lines.join("\n");
+            CompletionItem {
+                label: format!("keybind recipe: {}", label),
+                kind: Some(CompletionItemKind::SNIPPET),
+                detail: Some(description.to_string()),
+                documentation: Some(Documentation::MarkupContent(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: format!("```ghostty\n{}\n```", text),
+                })),
+                filter_text: Some(format!("keybind {}", label)),
+                // After the keys, so a blank line still lists options first
+                sort_text: Some(format!("zz_{}", label)),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(range, text))),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// The first documented example that's a valid choice, else the first choice.
+fn default_enum_value(opt: &ConfigOption) -> Option<&str> {
+    let choices = opt.enum_values.as_ref()?;
+    opt.examples
+        .iter()
+        .flatten()
+        .find(|ex| choices.contains(ex))
+        .or_else(|| choices.first())
+        .map(String::as_str)
+}
+
+/// Picks an icon by what the option configures so the list is easier to scan.
+fn key_completion_kind(opt: &ConfigOption) -> CompletionItemKind {
+    match opt.option_type.as_str() {
+        "keybind" => CompletionItemKind::EVENT,
+        "color" => CompletionItemKind::COLOR,
+        "path" => CompletionItemKind::FILE,
+        "command" => CompletionItemKind::FUNCTION,
+        "theme" => CompletionItemKind::REFERENCE,
+        "enum" | "boolean" | "flags" => CompletionItemKind::ENUM,
+        _ if opt.repeatable => CompletionItemKind::FIELD,
+        _ => CompletionItemKind::PROPERTY,
+    }
+}
+
+fn format_type_detail(opt: &ConfigOption) -> String {
+    let mut parts = vec![opt.option_type.clone()];
+    if opt.repeatable {
+        parts.push("repeatable (one value per line)".to_string());
+    }
+    if let Some(platforms) = &opt.platforms {
+        let names: Vec<&str> = platforms
+            .iter()
+            .map(|p| match p.as_str() {
+                "macos" => "macOS",
+                "linux" => "Linux",
+                other => other,
+            })
+            .collect();
+        parts.push(format!("{} only", names.join("/")));
+    }
+    if opt.deprecated {
+        parts.push("deprecated".to_string());
+    }
+    if opt.experimental {
+        parts.push("experimental".to_string());
+    }
+    parts.join(" | ")
+}
+
+/// Markdown describing an option, shown in completion documentation and hovers.
+pub fn format_key_documentation(key: &str, opt: &ConfigOption) -> String {
+    let mut doc = opt.description.clone();
+    if let Some(note) = &opt.note {
+        doc.push_str(&format!("\n\n> ⚠️ {}", note));
+    }
+    if let Some(examples) = &opt.examples {
+        doc.push_str("\n\n**Examples:**\n");
+        for ex in examples.iter().take(3) {
+            doc.push_str(&format!("- `{} = {}`\n", key, ex));
+        }
+    }
+    if let Some(enum_values) = &opt.enum_values {
+        doc.push_str("\n\n**Valid values:** ");
+        doc.push_str(&enum_values.join(", "));
+    }
+    if let Some(flags) = &opt.flags {
+        doc.push_str("\n\n**Features:** ");
+        doc.push_str(&flags.join(", "));
+    }
+    if let Some(see_also) = &opt.see_also {
+        let related: Vec<String> = see_also.iter().map(|k| format!("`{}`", k)).collect();
+        doc.push_str("\n\n**See also:** ");
+        doc.push_str(&related.join(", "));
+    }
+    doc
+}
+
+fn get_value_completions(
+    schema: &GhosttySchema,
+    config: &Config,
+    key: &str,
+    partial: &str,
+    partial_range: Range,
+    base_dir: Option<&Path>,
+) -> Vec<CompletionItem> {
+    let Some(opt) = schema.options.get(key) else {
+        return vec![];
+    };
+
+    // Paths are case-sensitive, so match them before lowercasing
+    if opt.has_type("path") && !config.safe_mode {
+        let items = get_path_completions(partial, base_dir);
+        if !items.is_empty() {
+            return items;
+        }
+    }
+
+    let partial_lower = partial.to_lowercase().trim().to_string();
+
+    let types: Vec<&str> = opt.types().collect();
+    if let [option_type] = types[..] {
+        return get_typed_value_completions(
+            schema,
+            config,
+            opt,
+            option_type,
+            &partial_lower,
+            partial_range,
+        );
+    }
+
+    // A union offers every member's values, then examples none of them cover
+    let mut seen = HashSet::new();
+    types
+        .iter()
+        .flat_map(|t| {
+            get_typed_value_completions(schema, config, opt, t, &partial_lower, partial_range)
+        })
+        .chain(get_example_completions(opt, &partial_lower))
+        .filter(|item| seen.insert(item.label.clone()))
+        .collect()
+}
+
+fn get_typed_value_completions(
+    schema: &GhosttySchema,
+    config: &Config,
+    opt: &ConfigOption,
+    option_type: &str,
+    partial: &str,
+    partial_range: Range,
+) -> Vec<CompletionItem> {
+    match option_type {
+        "boolean" => get_boolean_completions(partial),
+        "enum" => match config.enum_completion_style {
+            EnumCompletionStyle::SnippetChoice if partial.is_empty() => {
+                get_enum_choice_completion(opt).into_iter().collect()
+            }
+            _ => get_enum_completions(
+                opt,
+                partial,
+                config.normalize_enum_case.then_some(partial_range),
+            ),
+        },
+        "flags" => get_flag_completions(opt, partial),
+        "color" => get_colour_completions(schema, partial),
+        "keybind" => get_keybind_completions(schema, partial),
+        "theme" => get_theme_completions(partial, !config.safe_mode),
+        "regex" => get_regex_completions(partial),
+        "font-variation" => get_font_variation_completions(schema, partial),
+        "keyvalue-list" => get_keyvalue_completions(opt, partial),
+        _ => get_example_completions(opt, partial),
+    }
+}
+
+fn get_boolean_completions(partial: &str) -> Vec<CompletionItem> {
+    ["true", "false"]
+        .iter()
+        .filter(|v| partial.is_empty() || v.contains(partial))
+        .map(|v| simple_completion(v, CompletionItemKind::VALUE))
+        .collect()
+}
+
+/// With `replace_range`, accepting a value replaces what was typed with the
+/// schema's spelling, so `Block` becomes `block`.
+fn get_enum_completions(
+    opt: &ConfigOption,
+    partial: &str,
+    replace_range: Option<Range>,
+) -> Vec<CompletionItem> {
+    opt.enum_values
+        .as_ref()
+        .map(|vals| {
+            vals.iter()
+                .filter(|v| partial.is_empty() || v.to_lowercase().contains(partial))
+                .map(|v| {
+                    let mut item = simple_completion(v, CompletionItemKind::ENUM_MEMBER);
+                    item.text_edit = replace_range
+                        .map(|range| CompletionTextEdit::Edit(TextEdit::new(range, v.clone())));
+                    item
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// A single snippet whose placeholder lets the editor cycle through every
+/// value, for the `snippet-choice` style.
+fn get_enum_choice_completion(opt: &ConfigOption) -> Option<CompletionItem> {
+    let values = opt.enum_values.as_ref().filter(|v| !v.is_empty())?;
+    let choices: Vec<String> = values.iter().map(|v| escape_choice(v)).collect();
+    Some(CompletionItem {
+        label: values.join(" | "),
+        kind: Some(CompletionItemKind::ENUM),
+        detail: Some("Choose a value".to_string()),
+        insert_text: Some(format!("${{1|{}|}}", choices.join(","))),
+        insert_text_format: Some(InsertTextFormat::SNIPPET),
+        ..Default::default()
+    })
+}
+
+/// Completes the entry after the last comma, skipping features the list
+/// already sets either way.
+fn get_flag_completions(opt: &ConfigOption, partial: &str) -> Vec<CompletionItem> {
+    let Some(flags) = &opt.flags else {
+        return vec![];
+    };
+    let (chosen, current) = match partial.rsplit_once(',') {
+        Some((chosen, current)) => (chosen, current.trim()),
+        None => ("", partial),
+    };
+    let chosen: Vec<&str> = chosen.split(',').map(str::trim).map(flag_name).collect();
+
+    flags
+        .iter()
+        .filter(|flag| !chosen.contains(&flag.as_str()))
+        .flat_map(|flag| {
+            let negated = opt.negatable.then(|| (format!("no-{}", flag), "Disable"));
+            std::iter::once((flag.clone(), "Enable")).chain(negated)
+        })
+        .filter(|(label, _)| current.is_empty() || label.contains(current))
+        .map(|(label, verb)| {
+            let mut item = simple_completion(&label, CompletionItemKind::ENUM_MEMBER);
+            item.detail = Some(format!("{} feature", verb));
+            item
+        })
+        .collect()
+}
+
+fn get_colour_completions(schema: &GhosttySchema, partial: &str) -> Vec<CompletionItem> {
+    let mut items: Vec<CompletionItem> = vec![];
+
+    // Named colours from schema
+    if let Some(types) = &schema.types {
+        if let Some(color_type) = &types.color {
+            if let Some(named) = &color_type.named_values {
+                for name in named {
+                    if partial.is_empty() || name.to_lowercase().contains(partial) {
+                        items.push(simple_completion(name, CompletionItemKind::COLOR));
+                    }
+                }
+            }
+        }
+    }
+
+    // Hex colour template
+    if partial.is_empty() || "#".contains(partial) || partial.starts_with('#') {
+        let mut hex_item = simple_completion("#RRGGBB", CompletionItemKind::COLOR);
+        hex_item.detail = Some("Hex colour".to_string());
+        hex_item.insert_text = Some("#".to_string());
+        items.push(hex_item);
+    }
+
+    items
+}
+
+fn get_keybind_completions(schema: &GhosttySchema, partial: &str) -> Vec<CompletionItem> {
+    let mut items: Vec<CompletionItem> = vec![];
+
+    if let Some(types) = &schema.types {
+        if let Some(keybind) = &types.keybind {
+            let prefixes = keybind.prefixes.as_deref().unwrap_or_default();
+            let (used_prefixes, rest) = strip_keybind_prefixes(partial, prefixes);
+            let (trigger, action) = match split_keybind(rest) {
+                Some((trigger, action)) => (trigger, Some(action)),
+                None => (rest, None),
+            };
+
+            if action.is_none() {
+                // Prefixes (global:, all:, etc.) can only come before the trigger
+                if !trigger.contains('+') {
+                    for prefix in prefixes {
+                        if used_prefixes.contains(&prefix.as_str()) {
+                            continue;
+                        }
+                        let label = format!("{}:", prefix);
+                        if trigger.is_empty() || label.to_lowercase().contains(trigger) {
+                            let mut item = simple_completion(&label, CompletionItemKind::KEYWORD);
+                            item.detail = Some("Keybind prefix".to_string());
+                            items.push(item);
+                        }
+                    }
+                }
+
+                // Named keys, once a modifier has been typed for this step
+                let step = trigger.rsplit('>').next().unwrap_or("");
+                if let Some((_, current)) = step.rsplit_once('+') {
+                    for key in keybind.keys.iter().flatten() {
+                        if current.is_empty() || key.contains(current) {
+                            let mut item = simple_completion(key, CompletionItemKind::CONSTANT);
+                            item.detail = Some("Keyboard key".to_string());
+                            items.push(item);
+                        }
+                    }
+                }
+
+                // Modifiers (ctrl+, alt+, etc.), matched against the key being typed
+                if let Some(modifiers) = &keybind.modifiers {
+                    let current = trigger.rsplit('+').next().unwrap_or("");
+                    for modifier in modifiers {
+                        let label = format!("{}+", modifier);
+                        if current.is_empty() || label.to_lowercase().contains(current) {
+                            let mut item = simple_completion(&label, CompletionItemKind::KEYWORD);
+                            item.detail = Some("Modifier key".to_string());
+                            items.push(item);
+                        }
+                    }
+                }
+            }
+
+            // Actions (after =)
+            if action.is_some() || partial.is_empty() {
+                if let Some(actions) = &keybind.actions {
+                    let after_eq = action.unwrap_or("").trim();
+                    for action in actions {
+                        if after_eq.is_empty() || action.to_lowercase().contains(after_eq) {
+                            let mut item = simple_completion(action, CompletionItemKind::FUNCTION);
+                            item.detail = Some("Keybind action".to_string());
+                            items.push(item);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    items
+}
+
+/// `user_themes` adds the custom themes found in the config directory.
+fn get_theme_completions(partial: &str, user_themes: bool) -> Vec<CompletionItem> {
+    let mut items = vec![];
+    if partial.is_empty() || "auto".contains(partial) {
+        items.push(simple_completion("auto", CompletionItemKind::VALUE));
+    }
+
+    for (name, _) in themes::BUILTIN {
+        if partial.is_empty() || name.to_lowercase().contains(partial) {
+            let mut item = simple_completion(name, CompletionItemKind::VALUE);
+            item.detail = Some("Built-in theme".to_string());
+            item.data = value_data("theme", name);
+            items.push(item);
+        }
+    }
+
+    let custom = if user_themes {
+        paths::user_themes()
+    } else {
+        vec![]
+    };
+    for theme in custom {
+        if partial.is_empty() || theme.to_lowercase().contains(partial) {
+            let mut item = simple_completion(&theme, CompletionItemKind::VALUE);
+            item.detail = Some("Custom theme".to_string());
+            item.data = value_data("theme", &theme);
+            items.push(item);
+        }
+    }
+
+    // Light/dark combo snippet
+    if partial.is_empty() || "light:".contains(partial) {
+        let mut combo = CompletionItem {
+            label: "light:...,dark:...".to_string(),
+            kind: Some(CompletionItemKind::SNIPPET),
+            detail: Some("Light/dark theme combination".to_string()),
+            insert_text: Some("light:${1:Catppuccin Latte},dark:${2:Catppuccin Mocha}".to_string()),
+            insert_text_format: Some(InsertTextFormat::SNIPPET),
+            ..Default::default()
+        };
+        combo.documentation = Some(Documentation::String(
+            "Use different themes for light and dark mode".to_string(),
+        ));
+        items.push(combo);
+    }
+
+    items
+}
+
+/// Identifies a value item whose documentation is only built when the
+/// client resolves it.
+#[derive(Serialize, Deserialize)]
+struct ValueData {
+    key: String,
+    value: String,
+}
+
+fn value_data(key: &str, value: &str) -> Option<serde_json::Value> {
+    let data = ValueData {
+        key: key.to_string(),
+        value: value.to_string(),
+    };
+    serde_json::to_value(data).ok()
+}
+
+/// Drops the fields `completionDetail: "minimal"` leaves out, keeping what's
+/// needed to filter, sort and insert each item.
+pub fn strip_detail(items: &mut [CompletionItem]) {
+    for item in items {
+        item.detail = None;
+        item.documentation = None;
+        item.label_details = None;
+    }
+}
+
+/// Fills in the documentation of a value item from [`complete`], such as a
+/// theme's palette preview, and of a key item that [`strip_detail`] emptied.
+/// Other items are returned unchanged.
+pub fn resolve(
+    schema: &GhosttySchema,
+    config: &Config,
+    mut item: CompletionItem,
+) -> CompletionItem {
+    if item.detail.is_none() && item.documentation.is_none() && item.data.is_none() {
+        if let Some(opt) = schema
+            .options
+            .get(&item.label)
+            .filter(|opt| item.kind == Some(key_completion_kind(opt)))
+        {
+            item.detail = Some(format_type_detail(opt));
+            item.documentation = Some(Documentation::MarkupContent(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format_key_documentation(&item.label, opt),
+            }));
+            return item;
+        }
+    }
+    let Some(data) = item
+        .data
+        .clone()
+        .and_then(|d| serde_json::from_value::<ValueData>(d).ok())
+    else {
+        return item;
+    };
+    let preview = match data.key.as_str() {
+        "theme" => themes::preview(&data.value, !config.safe_mode),
+        _ => None,
+    };
+    if let Some(value) = preview {
+        item.documentation = Some(Documentation::MarkupContent(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        }));
+    }
+    item
+}
+
+/// Lists the entries of the directory being typed, after expanding `~` and
+/// environment variables. Hidden entries are only offered once a `.` is typed.
+fn get_path_completions(partial: &str, base_dir: Option<&Path>) -> Vec<CompletionItem> {
+    let partial = partial.trim_start_matches('?').trim_start_matches('"');
+    let (dir_part, name_prefix) = match partial.rfind('/') {
+        Some(i) => (&partial[..=i], &partial[i + 1..]),
+        None => ("", partial),
+    };
+    let dir = if dir_part.is_empty() {
+        base_dir.map(Path::to_path_buf)
+    } else {
+        paths::resolve(dir_part, base_dir)
+    };
+    let Some(Ok(entries)) = dir.map(std::fs::read_dir) else {
+        return vec![];
+    };
+
+    let mut items: Vec<CompletionItem> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !name.starts_with(name_prefix)
+                || (name.starts_with('.') && !name_prefix.starts_with('.'))
+            {
+                return None;
+            }
+            let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+            let (label, kind) = if is_dir {
+                (format!("{}/", name), CompletionItemKind::FOLDER)
+            } else {
+                (name, CompletionItemKind::FILE)
+            };
+            Some(simple_completion(&label, kind))
+        })
+        .collect();
+    items.sort_by(|a, b| a.label.cmp(&b.label));
+    items
+}
+
+/// Offers each known axis as a `tag=value` snippet until the `=` is typed.
+fn get_font_variation_completions(schema: &GhosttySchema, partial: &str) -> Vec<CompletionItem> {
+    if partial.contains('=') {
+        return vec![];
+    }
+    let axes = schema
+        .types
+        .as_ref()
+        .and_then(|t| t.font_variation.as_ref())
+        .and_then(|f| f.axes.as_deref())
+        .unwrap_or_default();
+
+    axes.iter()
+        .filter(|axis| {
+            partial.is_empty()
+                || axis.tag.contains(partial)
+                || axis.name.to_lowercase().contains(partial)
+        })
+        .map(|axis| CompletionItem {
+            label: axis.tag.clone(),
+            kind: Some(CompletionItemKind::SNIPPET),
+            detail: Some(format!("{} axis", axis.name)),
+            insert_text: Some(format!("{}=${{1:{}}}", axis.tag, axis.example)),
+            insert_text_format: Some(InsertTextFormat::SNIPPET),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// A `name=value` snippet for the pair being typed, followed by the
+/// schema's examples.
+fn get_keyvalue_completions(opt: &ConfigOption, partial: &str) -> Vec<CompletionItem> {
+    let current = partial.rsplit(',').next().unwrap_or_default().trim();
+    if current.contains('=') {
+        return vec![];
+    }
+    let pair = CompletionItem {
+        label: "name=value".to_string(),
+        kind: Some(CompletionItemKind::SNIPPET),
+        detail: Some("Key/value pair".to_string()),
+        insert_text: Some("${1:name}=${2:value}".to_string()),
+        insert_text_format: Some(InsertTextFormat::SNIPPET),
+        ..Default::default()
+    };
+    std::iter::once(pair)
+        .chain(get_example_completions(opt, current))
+        .collect()
+}
+
+fn get_regex_completions(partial: &str) -> Vec<CompletionItem> {
+    REGEX_SNIPPETS
+        .iter()
+        .filter(|(label, pattern)| {
+            partial.is_empty()
+                || label.to_lowercase().contains(partial)
+                || pattern.starts_with(partial)
+        })
+        .map(|(label, pattern)| CompletionItem {
+            label: label.to_string(),
+            kind: Some(CompletionItemKind::SNIPPET),
+            detail: Some(pattern.to_string()),
+            filter_text: Some(pattern.to_string()),
+            insert_text: Some(format!("${{1:{}}}", escape_snippet(pattern))),
+            insert_text_format: Some(InsertTextFormat::SNIPPET),
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn get_example_completions(opt: &ConfigOption, partial: &str) -> Vec<CompletionItem> {
+    opt.examples
+        .as_ref()
+        .map(|examples| {
+            examples
+                .iter()
+                .filter(|ex| partial.is_empty() || ex.to_lowercase().contains(partial))
+                .enumerate()
+                .map(|(i, ex)| {
+                    let mut item = simple_completion(ex, CompletionItemKind::VALUE);
+                    item.detail = Some("Example value (from schema)".to_string());
+                    // With nothing typed yet, Enter accepts the first documented example
+                    if partial.is_empty() && i == 0 {
+                        item.preselect = Some(true);
+                    }
+                    item
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn simple_completion(label: &str, kind: CompletionItemKind) -> CompletionItem {
+    CompletionItem {
+        label: label.to_string(),
+        kind: Some(kind),
+        ..Default::default()
+    }
+}
+
+/// Splits the line at its first `=`. Tabs and other whitespace around the key
+/// and before the value are trimmed like spaces, so `key\t=\tvalue` reads the
+/// same as `key = value`.
+fn parse_line_context(parsed: &ParsedLine, character: u32) -> LineContext {
+    let line = parsed.text.as_str();
+    let char_pos = byte_offset(line, character);
+
+    match parsed.kind {
+        LineKind::Comment => LineContext::Comment,
+        LineKind::Assignment { eq: eq_pos, .. } if char_pos > eq_pos => {
+            // Cursor is after equals - completing value
+            let key = line[..eq_pos].trim().to_string();
+            let value_part = &line[eq_pos + 1..char_pos];
+            // An opening quote isn't part of what's being completed
+            let partial = value_part.trim_start();
+            let partial = partial.strip_prefix('"').unwrap_or(partial);
+            LineContext::Value {
+                key,
+                start: utf16_col(line, char_pos - partial.len()),
+                partial: partial.to_string(),
+            }
+        }
+        LineKind::Assignment { eq: eq_pos, .. } => {
+            // Cursor is before or at equals - completing key
+            let key_part = &line[..char_pos];
+            LineContext::Key {
+                partial: key_part.trim().to_string(),
+                start: leading_whitespace_len(key_part),
+                existing_end: Some(utf16_col(line, line[..eq_pos].trim_end().len())),
+                word_end: character,
+            }
+        }
+        LineKind::Blank | LineKind::Other => {
+            // No equals - completing key
+            let key_part = &line[..char_pos];
+            let rest = &line[char_pos..];
+            let word_len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            LineContext::Key {
+                partial: key_part.trim().to_string(),
+                start: leading_whitespace_len(key_part),
+                existing_end: None,
+                word_end: utf16_col(line, char_pos + word_len),
+            }
+        }
+    }
+}
+
+/// What a completion at `position` fills in: `key`, or `value:<key>` for the
+/// value of `<key>`. Accepted items are counted separately per context.
+pub fn context_name(document: &ParsedDocument, position: Position) -> String {
+    let context = document
+        .line(position.line as usize)
+        .map(|line| parse_line_context(line, position.character));
+    match context {
+        Some(LineContext::Value { key, .. }) => format!("value:{}", key),
+        _ => "key".to_string(),
+    }
+}
+
+/// Escapes the characters that are special inside a snippet placeholder.
+fn escape_snippet(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('$', "\\$")
+        .replace('}', "\\}")
+}
+
+/// Choice entries also need `,` and `|` escaped.
+fn escape_choice(text: &str) -> String {
+    escape_snippet(text).replace(',', "\\,").replace('|', "\\|")
+}
+
+/// In UTF-16 units, since `trim_start` also strips multi-byte whitespace
+/// such as a no-break space.
+fn leading_whitespace_len(text: &str) -> u32 {
+    utf16_col(text, text.len() - text.trim_start().len())
+}
+
+#[derive(Debug)]
+enum LineContext {
+    Comment,
+    /// `start` is the column where the typed key begins. `existing_end` is
+    /// where the key ends when the line already has an `=`; otherwise
+    /// `word_end` is where the word under the cursor ends, so completing in
+    /// the middle of a key replaces all of it.
+    Key {
+        partial: String,
+        start: u32,
+        existing_end: Option<u32>,
+        word_end: u32,
+    },
+    /// `start` is the column where the typed value begins.
+    Value {
+        key: String,
+        partial: String,
+        start: u32,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn completions(text: &str, line: u32, col: u32) -> Vec<CompletionItem> {
+        crate::get_completions(&GhosttySchema::load(), text, line, col)
+    }
+
+    fn completions_with(config: &Config, text: &str, line: u32, col: u32) -> Vec<CompletionItem> {
+        let document = ParsedDocument::new(text);
+        complete(
+            &GhosttySchema::load(),
+            config,
+            &document,
+            Position::new(line, col),
+            None,
+        )
+    }
+
+    fn find<'a>(items: &'a [CompletionItem], label: &str) -> &'a CompletionItem {
+        items
+            .iter()
+            .find(|item| item.label == label)
+            .unwrap_or_else(|| panic!("no `{}` completion", label))
+    }
+
+    /// `line` after accepting `item`, which must replace a range on it.
+    fn accept(line: &str, item: &CompletionItem) -> String {
+        let Some(CompletionTextEdit::Edit(edit)) = &item.text_edit else {
+            panic!("`{}` has no text edit", item.label);
+        };
+        let start = byte_offset(line, edit.range.start.character);
+        let end = byte_offset(line, edit.range.end.character);
+        format!("{}{}{}", &line[..start], edit.new_text, &line[end..])
+    }
+
+    #[test]
+    fn accepting_a_key_replaces_the_typed_partial() {
+        let items = completions("font", 0, 4);
+        assert_eq!(
+            accept("font", find(&items, "font-family")),
+            "font-family = "
+        );
+    }
+
+    #[test]
+    fn indented_partial_keeps_its_indentation() {
+        let items = completions("  font", 0, 6);
+        assert_eq!(
+            accept("  font", find(&items, "font-size")),
+            "  font-size = "
+        );
+    }
+
+    #[test]
+    fn keybind_prefix_is_followed_by_modifiers_and_other_prefixes() {
+        let items = completions("keybind = global:", 0, 17);
+        let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+        assert!(labels.contains(&"ctrl+"));
+        assert!(labels.contains(&"all:"));
+        assert!(!labels.contains(&"global:"));
+    }
+
+    #[test]
+    fn keybind_prefix_then_modifier_offers_keys() {
+        let items = completions("keybind = global:ctrl+", 0, 22);
+        assert!(items.iter().any(|item| item.label == "arrow_up"));
+    }
+
+    #[test]
+    fn empty_value_preselects_the_first_example() {
+        let items = completions("font-size = ", 0, 12);
+        let preselected: Vec<&str> = items
+            .iter()
+            .filter(|item| item.preselect == Some(true))
+            .map(|item| item.label.as_str())
+            .collect();
+        assert_eq!(preselected, ["12"]);
+    }
+
+    #[test]
+    fn typed_value_preselects_nothing() {
+        let items = completions("font-size = 1", 0, 13);
+        assert!(items.iter().all(|item| item.preselect.is_none()));
+    }
+
+    #[test]
+    fn regex_values_offer_matcher_snippets() {
+        let items = completions("link = ", 0, 7);
+        let url = find(&items, "URL matcher");
+        assert_eq!(url.insert_text_format, Some(InsertTextFormat::SNIPPET));
+        assert_eq!(url.detail.as_deref(), Some(r#"https?://[^\s"'<>]+"#));
+    }
+
+    #[test]
+    fn regex_snippets_match_on_the_typed_pattern() {
+        let items = completions("link = https", 0, 12);
+        let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+        assert_eq!(labels, ["URL matcher"]);
+    }
+
+    #[test]
+    fn shell_integration_offers_its_modes() {
+        let items = completions("shell-integration = ", 0, 20);
+        assert!(items.iter().any(|item| item.label == "fish"));
+        assert!(items.iter().any(|item| item.label == "detect"));
+    }
+
+    #[test]
+    fn feature_list_completes_the_entry_after_the_last_comma() {
+        let items = completions("shell-integration-features = cursor,s", 0, 37);
+        let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+        assert!(labels.contains(&"sudo"));
+        assert!(labels.contains(&"ssh-env"));
+        assert!(!labels.contains(&"cursor"));
+    }
+
+    #[test]
+    fn font_variation_offers_axes_by_tag_or_name() {
+        let items = completions("font-variation = weig", 0, 21);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].label, "wght");
+        assert_eq!(items[0].insert_text.as_deref(), Some("wght=${1:500}"));
+    }
+
+    #[test]
+    fn font_variation_stops_after_the_equals() {
+        assert!(completions("font-variation = wght=", 0, 22).is_empty());
+    }
+
+    #[test]
+    fn enum_key_can_insert_its_value_too() {
+        let config = Config {
+            complete_key_with_value: true,
+            ..Config::default()
+        };
+        let items = completions_with(&config, "cursor-st", 0, 9);
+        let item = find(&items, "cursor-style");
+        assert_eq!(item.insert_text_format, Some(InsertTextFormat::SNIPPET));
+        assert_eq!(accept("cursor-st", item), "cursor-style = ${1:block}");
+
+        // Keys without choices still insert just `key = `
+        let items = completions_with(&config, "font-si", 0, 7);
+        assert_eq!(accept("font-si", find(&items, "font-size")), "font-size = ");
+    }
+
+    #[test]
+    fn value_is_left_out_by_default() {
+        let items = completions("cursor-st", 0, 9);
+        assert_eq!(
+            accept("cursor-st", find(&items, "cursor-style")),
+            "cursor-style = "
+        );
+    }
+
+    #[test]
+    fn startup_enums_offer_their_choices() {
+        let labels = |text: &str| -> Vec<String> {
+            let col = text.len() as u32;
+            completions(text, 0, col)
+                .into_iter()
+                .map(|item| item.label)
+                .collect()
+        };
+        assert_eq!(labels("gtk-tabs-location = "), ["bottom", "hidden", "top"]);
+        assert_eq!(
+            labels("linux-cgroup = "),
+            ["always", "never", "single-instance"]
+        );
+        assert_eq!(labels("macos-hidden = "), ["always", "never"]);
+        assert_eq!(labels("fullscreen = "), ["false", "true"]);
+    }
+
+    #[test]
+    fn union_types_offer_each_members_values_once() {
+        let items = completions("background-blur = ", 0, 18);
+        let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+        assert!(labels.contains(&"true"));
+        assert!(labels.contains(&"false"));
+        let unique: HashSet<&str> = labels.iter().copied().collect();
+        assert_eq!(unique.len(), labels.len());
+    }
+
+    #[test]
+    fn keybind_line_snippet_replaces_the_typed_key() {
+        let items = completions("keyb", 0, 4);
+        let snippet = find(&items, "keybind = …");
+        assert_eq!(snippet.insert_text_format, Some(InsertTextFormat::SNIPPET));
+        assert_eq!(accept("keyb", snippet), "keybind = ${1:ctrl+a}=${2:action}");
+    }
+
+    #[test]
+    fn line_snippets_need_a_line_without_equals() {
+        let items = completions("keyb = x", 0, 4);
+        assert!(items.iter().all(|item| item.label != "keybind = …"));
+    }
+
+    #[test]
+    fn snippet_choice_style_offers_one_item_cycling_through_values() {
+        let config = Config {
+            enum_completion_style: EnumCompletionStyle::SnippetChoice,
+            ..Config::default()
+        };
+        let items = completions_with(&config, "cursor-style = ", 0, 15);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].label, "block | bar | underline | block_hollow");
+        assert_eq!(
+            items[0].insert_text.as_deref(),
+            Some("${1|block,bar,underline,block_hollow|}")
+        );
+
+        // Once something is typed, the values are filtered as usual
+        let items = completions_with(&config, "cursor-style = b", 0, 16);
+        let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+        assert_eq!(labels, ["bar", "block", "block_hollow"]);
+    }
+
+    #[test]
+    fn choice_values_are_escaped() {
+        assert_eq!(escape_choice("a,b|c$d}"), "a\\,b\\|c\\$d\\}");
+    }
+
+    #[test]
+    fn keys_already_set_sort_last_when_asked() {
+        let config = Config {
+            deprioritize_set_keys: true,
+            ..Config::default()
+        };
+        let text = "font-size = 12\nfont-family = A\nfont-s";
+        let items = completions_with(&config, text, 2, 6);
+        let size = find(&items, "font-size");
+        assert_eq!(size.sort_text.as_deref(), Some("y_font-size"));
+        assert_eq!(
+            size.label_details
+                .as_ref()
+                .and_then(|d| d.description.as_deref()),
+            Some("already set")
+        );
+        assert_eq!(
+            items.last().map(|item| item.label.as_str()),
+            Some("font-size")
+        );
+
+        // Repeatable keys can be set again, so they keep their place
+        let items = completions_with(&config, "font-size = 12\nfont-family = A\nfont", 2, 4);
+        assert!(find(&items, "font-family").sort_text.is_none());
+    }
+
+    #[test]
+    fn keys_already_set_sort_last_on_the_line_after_a_trailing_newline() {
+        let config = Config {
+            deprioritize_set_keys: true,
+            ..Config::default()
+        };
+        let items = completions_with(&config, "font-size = 12\n", 1, 0);
+        assert_eq!(
+            find(&items, "font-size").sort_text.as_deref(),
+            Some("y_font-size")
+        );
+        let position = |label: &str| items.iter().position(|item| item.label == label);
+        assert!(position("font-size") > position("window-padding-x"));
+    }
+
+    #[test]
+    fn the_line_being_edited_does_not_count_as_set() {
+        let config = Config {
+            deprioritize_set_keys: true,
+            ..Config::default()
+        };
+        let items = completions_with(&config, "font-size = 12", 0, 6);
+        assert!(find(&items, "font-size").sort_text.is_none());
+    }
+
+    #[test]
+    fn binary_file_has_no_completions() {
+        assert!(completions("\u{1}\u{2}\u{3}\nfont", 1, 4).is_empty());
+    }
+
+    #[test]
+    fn keyvalue_lists_complete_the_pair_after_the_last_comma() {
+        let items = completions("env = EDITOR=vim,", 0, 17);
+        let pair = find(&items, "name=value");
+        assert_eq!(pair.insert_text.as_deref(), Some("${1:name}=${2:value}"));
+        assert!(items.iter().any(|item| item.label == "EDITOR=vim"));
+
+        assert!(completions("env = EDITOR=vi", 0, 15).is_empty());
+    }
+
+    #[test]
+    fn order_does_not_depend_on_the_schema_map() {
+        let items = completions("", 0, 0);
+        let labels = |items: &[CompletionItem]| -> Vec<String> {
+            items.iter().map(|item| item.label.clone()).collect()
+        };
+        assert_eq!(labels(&items), labels(&completions("", 0, 0)));
+
+        let keys: Vec<(&str, &str)> = items
+            .iter()
+            .map(|item| {
+                (
+                    item.sort_text.as_deref().unwrap_or(&item.label),
+                    item.label.as_str(),
+                )
+            })
+            .collect();
+        assert!(keys.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(items.last().unwrap().label.starts_with("keybind recipe"));
+    }
+
+    #[test]
+    fn sort_text_comes_before_the_label() {
+        let mut items = vec![
+            simple_completion("b", CompletionItemKind::VALUE),
+            CompletionItem {
+                sort_text: Some("z".to_string()),
+                ..simple_completion("a", CompletionItemKind::VALUE)
+            },
+            simple_completion("c", CompletionItemKind::VALUE),
+            CompletionItem {
+                sort_text: Some("!".to_string()),
+                ..simple_completion("d", CompletionItemKind::VALUE)
+            },
+        ];
+        sort_completions(&mut items);
+        let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+        assert_eq!(labels, ["d", "b", "c", "a"]);
+    }
+
+    #[test]
+    fn palette_suggests_the_next_unused_index() {
+        let text = "palette = 0=#000000\npalette = 2=#00ff00\npalette = 1=#ff0000\npalette = ";
+        let items = completions(text, 3, 10);
+        let next = find(&items, "3=");
+        assert_eq!(next.preselect, Some(true));
+        assert_eq!(next.insert_text.as_deref(), Some("3=${1:#000000}"));
+        assert!(
+            items
+                .iter()
+                .filter(|item| item.preselect == Some(true))
+                .count()
+                == 1
+        );
+    }
+
+    #[test]
+    fn palette_fills_gaps_and_ignores_the_current_line() {
+        let items = completions("palette = 1=#ff0000\npalette = 0=", 1, 10);
+        assert!(items.iter().any(|item| item.label == "0="));
+    }
+
+    #[test]
+    fn palette_stops_suggesting_once_the_index_is_typed() {
+        let items = completions("palette = 0=#000000\npalette = 1=", 1, 12);
+        assert!(items.iter().all(|item| !item.label.ends_with('=')));
+    }
+
+    #[test]
+    fn theme_previews_are_left_to_resolve() {
+        let schema = GhosttySchema::load();
+        let items = completions("theme = Catppuccin Mo", 0, 21);
+        let item = find(&items, "Catppuccin Mocha").clone();
+        assert!(item.documentation.is_none());
+        assert!(item.data.is_some());
+
+        let resolved = resolve(&schema, &Config::default(), item);
+        let Some(Documentation::MarkupContent(preview)) = resolved.documentation else {
+            panic!("theme wasn't previewed");
+        };
+        assert!(preview.value.contains('#'));
+    }
+
+    #[test]
+    fn resolve_leaves_other_items_alone() {
+        let schema = GhosttySchema::load();
+        let item = simple_completion("block", CompletionItemKind::ENUM_MEMBER);
+        assert_eq!(resolve(&schema, &Config::default(), item.clone()), item);
+
+        // Only names inside the themes directory are read
+        let outside = CompletionItem {
+            data: value_data("theme", "../config"),
+            ..simple_completion("../config", CompletionItemKind::VALUE)
+        };
+        assert!(resolve(&schema, &Config::default(), outside)
+            .documentation
+            .is_none());
+    }
+
+    #[test]
+    fn keybind_keys_are_offered_after_a_modifier() {
+        let items = completions("keybind = ctrl+arrow", 0, 20);
+        let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+        assert!(labels.contains(&"arrow_up"));
+        assert_eq!(
+            find(&items, "arrow_up").detail.as_deref(),
+            Some("Keyboard key")
+        );
+
+        // Without a modifier the first word could still be a prefix
+        let items = completions("keybind = arrow", 0, 15);
+        assert!(items.iter().all(|item| item.label != "arrow_up"));
+    }
+
+    #[test]
+    fn schema_notes_are_called_out_in_documentation() {
+        let schema = GhosttySchema::load();
+        let doc = format_key_documentation("linux-cgroup", &schema.options["linux-cgroup"]);
+        assert!(doc.contains("\n\n> ⚠️ Requires restart"), "{}", doc);
+        assert!(doc.find("> ⚠️") < doc.find("**Examples:**"));
+
+        let doc = format_key_documentation("font-size", &schema.options["font-size"]);
+        assert!(!doc.contains("⚠️"));
+    }
+
+    #[test]
+    fn experimental_options_are_marked_in_the_detail() {
+        let opt: ConfigOption = serde_json::from_value(serde_json::json!({
+            "type": "boolean",
+            "description": "Something new.",
+            "experimental": true
+        }))
+        .unwrap();
+        assert_eq!(format_type_detail(&opt), "boolean | experimental");
+    }
+
+    #[test]
+    fn negatable_flags_offer_both_forms() {
+        let items = completions("shell-integration-features = sudo, ", 0, 35);
+        let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+        assert!(labels.contains(&"cursor"));
+        assert!(labels.contains(&"no-cursor"));
+        assert!(!labels.contains(&"sudo"));
+        assert!(!labels.contains(&"no-sudo"));
+    }
+
+    #[test]
+    fn multi_byte_indentation_is_measured_in_utf16() {
+        let line = "\u{a0}\u{3000}font-fam";
+        let items = completions(line, 0, 10);
+        let Some(CompletionTextEdit::Edit(edit)) = &find(&items, "font-family").text_edit else {
+            panic!("`font-family` has no text edit");
+        };
+        assert_eq!(edit.range.start.character, 2);
+        assert_eq!(
+            accept(line, find(&items, "font-family")),
+            "\u{a0}\u{3000}font-family = "
+        );
+    }
+
+    #[test]
+    fn tabs_separate_keys_and_values_like_spaces() {
+        let items = completions("cursor-style\t=\tbl", 0, 17);
+        let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+        assert_eq!(labels, ["block", "block_hollow"]);
+    }
+
+    #[test]
+    fn enum_values_can_replace_the_typed_casing() {
+        let config = Config {
+            normalize_enum_case: true,
+            ..Config::default()
+        };
+        let items = completions_with(&config, "cursor-style = Blo", 0, 18);
+        assert_eq!(
+            accept("cursor-style = Blo", find(&items, "block")),
+            "cursor-style = block"
+        );
+
+        // Off by default, leaving the client to insert the label
+        let items = completions("cursor-style = Blo", 0, 18);
+        assert!(find(&items, "block").text_edit.is_none());
+    }
+
+    #[test]
+    fn keys_match_on_the_starts_of_their_words() {
+        assert!(key_matches("window-padding-x", "wpx"));
+        assert!(key_matches("window-padding-x", "padx"));
+        assert!(key_matches("window-padding-x", "winpad"));
+        assert!(key_matches("window-padding-x", "ding"));
+        assert!(!key_matches("window-padding-x", "xpw"));
+        assert!(!key_matches("window-padding-x", "wpy"));
+
+        let items = completions("wpx", 0, 3);
+        let item = find(&items, "window-padding-x");
+        assert_eq!(item.filter_text.as_deref(), Some("window-padding-x wpx"));
+        assert_eq!(accept("wpx", item), "window-padding-x = ");
+    }
+
+    #[test]
+    fn keybind_recipes_are_valid_keybinds() {
+        for (label, _, bindings) in KEYBIND_RECIPES {
+            let text: String = bindings
+                .iter()
+                .map(|binding| format!("keybind = {}\n", binding))
+                .collect();
+            let diagnostics = crate::lint(&GhosttySchema::load(), &text);
+            assert!(diagnostics.is_empty(), "{}: {:?}", label, diagnostics);
+        }
+    }
+
+    #[test]
+    fn keybind_recipe_inserts_one_line_per_binding() {
+        let label = "keybind recipe: vim-style pane movement";
+        let items = completions("keyb", 0, 4);
+        let inserted = accept("keyb", find(&items, label));
+        assert!(inserted.starts_with("keybind = ctrl+h=goto_split:left\nkeybind = ctrl+j="));
+        assert_eq!(inserted.lines().count(), 8);
+
+        // After `keybind =` the first binding completes the line
+        let items = completions("keybind = ", 0, 10);
+        let inserted = accept("keybind = ", find(&items, label));
+        assert!(inserted.starts_with("keybind = ctrl+h=goto_split:left\nkeybind = ctrl+j="));
+
+        let items = completions("font", 0, 4);
+        assert!(items.iter().all(|item| item.label != label));
+    }
+
+    #[test]
+    fn value_completion_starts_after_an_opening_quote() {
+        let items = completions("cursor-style = \"bl", 0, 18);
+        let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+        assert_eq!(labels, ["block", "block_hollow"]);
+    }
+
+    #[test]
+    fn stripped_key_items_get_their_documentation_back_on_resolve() {
+        let schema = GhosttySchema::load();
+        let mut items = completions("font-fam", 0, 8);
+        let full = find(&items, "font-family").clone();
+        strip_detail(&mut items);
+        let stripped = find(&items, "font-family").clone();
+        assert_eq!((&stripped.detail, &stripped.documentation), (&None, &None));
+        assert_eq!(stripped.text_edit, full.text_edit);
+
+        let resolved = resolve(&schema, &Config::default(), stripped);
+        assert_eq!(resolved.detail, full.detail);
+        assert_eq!(resolved.documentation, full.documentation);
+    }
+
+    #[test]
+    fn completing_mid_key_replaces_the_whole_key() {
+        let items = completions("font-family", 0, 8);
+        assert_eq!(
+            accept("font-family", find(&items, "font-family")),
+            "font-family = "
+        );
+
+        let items = completions("font-family = Iosevka", 0, 8);
+        assert_eq!(
+            accept("font-family = Iosevka", find(&items, "font-family-bold")),
+            "font-family-bold = Iosevka"
+        );
+    }
+
+    #[test]
+    fn window_and_option_key_enums_offer_their_values() {
+        for (key, values) in [
+            ("window-save-state", &["default", "never", "always"][..]),
+            ("window-colorspace", &["srgb", "display-p3"]),
+            ("macos-option-as-alt", &["true", "false", "left", "right"]),
+        ] {
+            let line = format!("{} = ", key);
+            let items = completions(&line, 0, line.len() as u32);
+            let mut labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+            labels.sort();
+            let mut expected = values.to_vec();
+            expected.sort();
+            assert_eq!(labels, expected, "{}", key);
+        }
+    }
+
+    #[test]
+    fn window_decoration_offers_its_modes() {
+        let items = completions("window-decoration = ", 0, 20);
+        let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+        for mode in ["auto", "client", "server", "none"] {
+            assert!(labels.contains(&mode), "{}", mode);
+        }
+    }
+
+    #[test]
+    fn only_snippet_items_set_an_insert_format() {
+        let items = completions("font-fam", 0, 8);
+        assert_eq!(find(&items, "font-family").insert_text_format, None);
+
+        let items = completions("keybind = x\nkeyb", 1, 4);
+        assert_eq!(
+            find(&items, "keybind = …").insert_text_format,
+            Some(InsertTextFormat::SNIPPET)
+        );
+
+        // A key inserted with its default value has a placeholder to fill in
+        let config = Config {
+            complete_key_with_value: true,
+            ..Config::default()
+        };
+        let items = completions_with(&config, "cursor-st", 0, 9);
+        assert_eq!(
+            find(&items, "cursor-style").insert_text_format,
+            Some(InsertTextFormat::SNIPPET)
+        );
+        let items = completions_with(&config, "font-si", 0, 7);
+        assert_eq!(find(&items, "font-size").insert_text_format, None);
+    }
+}
@@ -0,0 +1,221 @@
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use tower_lsp::lsp_types::TraceValue;
+
+/// User-facing settings, read from `initializationOptions` and refreshed on
+/// `workspace/didChangeConfiguration`. Unknown fields are ignored; a malformed
+/// field falls back to its default without affecting the others.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Config {
+    /// Maximum number of completion items returned before the list is marked incomplete.
+    pub max_completions: usize,
+    /// When diagnostics are published: on every change, only on save, or never.
+    pub diagnostics_on: DiagnosticsOn,
+    /// Check that `command`/`initial-command` name an executable that exists.
+    /// Off by default because it touches the filesystem.
+    pub check_executables: bool,
+    /// Check that `path` values point at a file that exists, after expanding
+    /// `~` and environment variables. Off by default for the same reason.
+    pub check_paths: bool,
+    /// Suggest writing keybind modifiers in ctrl+alt+shift+super order.
+    pub check_modifier_order: bool,
+    /// Check every value against its option's type and report mismatches as
+    /// errors instead of warnings.
+    pub strict: bool,
+    /// List completions accepted earlier in the session first. Off by default
+    /// because every item then carries a command the client runs on accept.
+    pub rank_recent_completions: bool,
+    /// Accepting an enum key inserts `key = <value>` with the value selected,
+    /// instead of just `key = `.
+    pub complete_key_with_value: bool,
+    /// List keys the document already sets after the others. Repeatable keys
+    /// are never moved.
+    pub deprioritize_set_keys: bool,
+    /// Show each keybind action's description as an inlay hint after it.
+    pub keybind_action_hints: bool,
+    /// Publish diagnostics for every Ghostty config found in the workspace
+    /// folders, not just the open ones.
+    pub workspace_diagnostics: bool,
+    /// Glob patterns, relative to each workspace folder, skipped when scanning.
+    pub exclude_globs: Vec<String>,
+    /// Ghostty release to validate against, such as `1.1`. When unset the
+    /// server asks `ghostty --version`.
+    pub ghostty_version: Option<String>,
+    /// Initial `$/logTrace` level, for clients that can't set `trace` in
+    /// `initialize`. Overrides the client's value when set.
+    pub trace: Option<TraceValue>,
+    /// Exit after this many seconds with no document open. Read once at
+    /// startup; unset keeps the server running.
+    pub idle_shutdown_secs: Option<u64>,
+    /// Never touch the filesystem or run programs: no path or custom theme
+    /// completion, executable or path checks, includes or workspace scanning.
+    pub safe_mode: bool,
+    /// How enum values are offered: one item each, or a single snippet whose
+    /// placeholder cycles through them.
+    pub enum_completion_style: EnumCompletionStyle,
+    /// Accepting an enum value replaces the typed text with the schema's
+    /// casing. Off keeps whatever the client inserts.
+    pub normalize_enum_case: bool,
+    /// Also run `ghostty +validate-config` on each document and report what
+    /// it rejects. Off by default because it starts a process per check.
+    pub use_ghostty_validator: bool,
+    /// Send `$/ghostty/diagnosticsSummary` with problem counts across every
+    /// document whenever diagnostics are published, for status bar
+    /// integrations.
+    pub diagnostics_summary: bool,
+    /// `minimal` leaves `detail` and `documentation` out of completion items
+    /// to keep responses small; key documentation comes back on resolve.
+    pub completion_detail: CompletionDetail,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticsOn {
+    Change,
+    Save,
+    Off,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EnumCompletionStyle {
+    Items,
+    SnippetChoice,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompletionDetail {
+    Full,
+    Minimal,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_completions: 200,
+            diagnostics_on: DiagnosticsOn::Change,
+            check_executables: false,
+            check_paths: false,
+            check_modifier_order: false,
+            strict: false,
+            rank_recent_completions: false,
+            complete_key_with_value: false,
+            deprioritize_set_keys: false,
+            keybind_action_hints: true,
+            workspace_diagnostics: false,
+            exclude_globs: vec![],
+            ghostty_version: None,
+            trace: None,
+            idle_shutdown_secs: None,
+            safe_mode: false,
+            enum_completion_style: EnumCompletionStyle::Items,
+            normalize_enum_case: false,
+            use_ghostty_validator: false,
+            diagnostics_summary: false,
+            completion_detail: CompletionDetail::Full,
+        }
+    }
+}
+
+impl Config {
+    pub fn from_value(value: Option<Value>) -> Self {
+        Self::parse(value).0
+    }
+
+    /// Like [`Config::from_value`], also describing each setting that was
+    /// malformed and left at its default.
+    pub fn parse(value: Option<Value>) -> (Self, Vec<String>) {
+        let mut fields = match value {
+            None | Some(Value::Null) => return (Self::default(), vec![]),
+            Some(Value::Object(fields)) => fields,
+            Some(other) => {
+                let problem = format!("Expected settings to be an object, got `{}`", other);
+                return (Self::default(), vec![problem]);
+            }
+        };
+
+        // Each field is tried on its own, so one bad value only loses itself
+        let mut problems = vec![];
+        fields.retain(|name, field| {
+            let single = Value::Object(Map::from_iter([(name.clone(), field.clone())]));
+            match serde_json::from_value::<Self>(single) {
+                Ok(_) => true,
+                Err(e) => {
+                    problems.push(format!("Ignoring setting `{}`: {}", name, e));
+                    false
+                }
+            }
+        });
+        let mut config: Self = serde_json::from_value(Value::Object(fields)).unwrap_or_default();
+        // An empty list would stop completion altogether
+        if config.max_completions == 0 {
+            problems.push("Ignoring setting `maxCompletions`: it must be at least 1".to_string());
+            config.max_completions = Self::default().max_completions;
+        }
+        (config, problems)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn missing_settings_are_defaults() {
+        let (config, problems) = Config::parse(None);
+        assert_eq!(config.max_completions, Config::default().max_completions);
+        assert!(problems.is_empty());
+    }
+
+    #[test]
+    fn reads_camel_case_fields() {
+        let config = Config::from_value(Some(json!({
+            "maxCompletions": 5,
+            "diagnosticsOn": "save",
+            "strict": true,
+        })));
+        assert_eq!(config.max_completions, 5);
+        assert_eq!(config.diagnostics_on, DiagnosticsOn::Save);
+        assert!(config.strict);
+    }
+
+    #[test]
+    fn malformed_field_keeps_the_others() {
+        let (config, problems) = Config::parse(Some(json!({
+            "maxCompletions": "200",
+            "strict": true,
+            "checkPaths": true,
+        })));
+        assert_eq!(config.max_completions, Config::default().max_completions);
+        assert!(config.strict);
+        assert!(config.check_paths);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("maxCompletions"));
+    }
+
+    #[test]
+    fn zero_max_completions_is_rejected() {
+        let (config, problems) = Config::parse(Some(json!({ "maxCompletions": 0 })));
+        assert_eq!(config.max_completions, Config::default().max_completions);
+        assert_eq!(
+            problems,
+            ["Ignoring setting `maxCompletions`: it must be at least 1"]
+        );
+    }
+
+    #[test]
+    fn unknown_fields_are_ignored() {
+        let (config, problems) = Config::parse(Some(json!({ "notASetting": 1, "strict": true })));
+        assert!(config.strict);
+        assert!(problems.is_empty());
+    }
+
+    #[test]
+    fn non_object_settings_are_reported() {
+        let (_, problems) = Config::parse(Some(json!([1, 2])));
+        assert_eq!(problems.len(), 1);
+    }
+}
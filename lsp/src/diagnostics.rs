@@ -0,0 +1,2160 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tower_lsp::lsp_types::{
+    CodeDescription, Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Location,
+    NumberOrString, Position, Range, TextEdit, Url,
+};
+
+use crate::config::Config;
+use crate::directives;
+use crate::executables::{program_token, ExecutableCache};
+use crate::keybind::check_keybind;
+use crate::parse::{
+    flag_name, looks_like_text, split_keybind, strip_keybind_prefixes, utf16_col, Assignment,
+    ParsedDocument,
+};
+use crate::paths;
+use crate::schema::{ConfigOption, GhosttySchema, Requirement};
+
+pub const SOURCE: &str = "ghostty-lsp";
+
+/// Code shared by every "value doesn't fit the option's type" diagnostic, so
+/// strict mode can escalate them in one place.
+const INVALID_VALUE_CODE: &str = "invalid-value";
+
+/// Codes for the other diagnostic categories. They're what a
+/// `# ghostty-lsp: disable <code>` directive names.
+const UNKNOWN_KEY_CODE: &str = "unknown-key";
+const TRAILING_WHITESPACE_CODE: &str = "trailing-whitespace";
+const KEYBIND_CODE: &str = "keybind";
+const MISSING_EXECUTABLE_CODE: &str = "missing-executable";
+const MISSING_PATH_CODE: &str = "missing-path";
+const WINDOWS_PATH_CODE: &str = "windows-path";
+const FONT_FAMILY_CODE: &str = "font-family";
+const SYNTAX_CODE: &str = "syntax";
+const MULTIPLE_ENTRIES_CODE: &str = "multiple-entries";
+const NOT_A_CONFIG_CODE: &str = "not-a-config";
+const VALUE_TOO_LONG_CODE: &str = "value-too-long";
+const NO_EFFECT_CODE: &str = "no-effect";
+const GHOSTTY_CODE: &str = "ghostty";
+const LINE_CONTINUATION_CODE: &str = "line-continuation";
+
+/// Every diagnostic code with the rule it reports. The README's "Diagnostic
+/// codes" table, which each diagnostic links to, mirrors this list.
+pub const CODES: &[(&str, &str)] = &[
+    (
+        INVALID_VALUE_CODE,
+        "A value doesn't fit its option's type: an unknown enum value, a number out of range, a malformed colour or regex, or a `yes`/`no` boolean",
+    ),
+    (UNKNOWN_KEY_CODE, "The key isn't a Ghostty option"),
+    (
+        SYNTAX_CODE,
+        "The line isn't a `key = value` assignment Ghostty can read, such as `key: value` or a `!`-negated key",
+    ),
+    (
+        KEYBIND_CODE,
+        "A keybind has duplicate or misordered modifiers, a prefix in the wrong place, or a missing or unexpected action argument",
+    ),
+    (
+        MULTIPLE_ENTRIES_CODE,
+        "Several entries of a repeatable key such as `palette` are written comma-separated on one line",
+    ),
+    (FONT_FAMILY_CODE, "A bold or italic font family is set without a base `font-family`"),
+    (MISSING_EXECUTABLE_CODE, "The program a `command` starts isn't on `PATH`"),
+    (MISSING_PATH_CODE, "A path value points at a file that doesn't exist"),
+    (WINDOWS_PATH_CODE, "A path uses Windows `\\` separators or a drive letter on Linux or macOS"),
+    (
+        VALUE_TOO_LONG_CODE,
+        "A value is longer than its option plausibly needs, which usually means a paste went wrong",
+    ),
+    (
+        NO_EFFECT_CODE,
+        "An option does nothing because the option it depends on isn't set the way it needs",
+    ),
+    (
+        LINE_CONTINUATION_CODE,
+        "A value ends in `\\`, but Ghostty doesn't continue values onto the next line",
+    ),
+    (
+        GHOSTTY_CODE,
+        "`ghostty +validate-config` rejected the file, with `useGhosttyValidator` on",
+    ),
+    (TRAILING_WHITESPACE_CODE, "The line ends in whitespace"),
+    (
+        NOT_A_CONFIG_CODE,
+        "The file looks binary rather than text, so it isn't checked further",
+    ),
+];
+
+const CODES_URL: &str = "https://github.com/Else00/ghostty-zed-extension#diagnostic-codes";
+
+/// The spellings Ghostty's boolean parser accepts.
+const BOOLEAN_VALUES: &[&str] = &["true", "false", "1", "0", "t", "f", "T", "F"];
+
+/// The choices of `clipboard-read` and `clipboard-write`, easily mixed up with
+/// their neighbours `clipboard-paste-protection` and
+/// `clipboard-paste-bracketed-safe`, which are booleans.
+const PERMISSION_VALUES: &[&str] = &["allow", "deny", "ask"];
+
+/// Longer units first so `ms` isn't read as `m` followed by `s`.
+const DURATION_UNITS: &[&str] = &["ms", "us", "µs", "ns", "y", "w", "d", "h", "m", "s"];
+
+const FONT_STYLE_KEYS: &[&str] = &[
+    "font-family-bold",
+    "font-family-italic",
+    "font-family-bold-italic",
+];
+
+/// A quick fix carried in a diagnostic's `data`, so code actions can offer it
+/// without re-running validation.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Fix {
+    pub title: String,
+    pub edit: TextEdit,
+}
+
+impl Fix {
+    pub fn from_diagnostic(diagnostic: &Diagnostic) -> Option<Self> {
+        serde_json::from_value(diagnostic.data.clone()?).ok()
+    }
+}
+
+fn span(line_num: usize, line: &str, start: usize, len: usize) -> Range {
+    Range {
+        start: Position::new(line_num as u32, utf16_col(line, start)),
+        end: Position::new(line_num as u32, utf16_col(line, start + len)),
+    }
+}
+
+fn diagnostic(
+    range: Range,
+    severity: DiagnosticSeverity,
+    code: &str,
+    message: String,
+) -> Diagnostic {
+    debug_assert!(
+        CODES.iter().any(|(known, _)| *known == code),
+        "unregistered diagnostic code {}",
+        code
+    );
+    Diagnostic {
+        range,
+        severity: Some(severity),
+        code: Some(NumberOrString::String(code.to_string())),
+        code_description: Url::parse(CODES_URL)
+            .ok()
+            .map(|href| CodeDescription { href }),
+        source: Some(SOURCE.to_string()),
+        message,
+        ..Default::default()
+    }
+}
+
+fn invalid_value(range: Range, message: String) -> Diagnostic {
+    diagnostic(
+        range,
+        DiagnosticSeverity::WARNING,
+        INVALID_VALUE_CODE,
+        message,
+    )
+}
+
+fn with_fix(mut diagnostic: Diagnostic, title: &str, edit: TextEdit) -> Diagnostic {
+    let fix = Fix {
+        title: title.to_string(),
+        edit,
+    };
+    diagnostic.data = serde_json::to_value(fix).ok();
+    diagnostic
+}
+
+pub fn validate_document(
+    schema: &GhosttySchema,
+    document: &ParsedDocument,
+    config: &Config,
+    executables: &ExecutableCache,
+    uri: &Url,
+) -> Vec<Diagnostic> {
+    // One note instead of an unknown-key error for every line of garbage
+    let text = document.text();
+    if !looks_like_text(text) {
+        return vec![diagnostic(
+            Range::default(),
+            DiagnosticSeverity::INFORMATION,
+            NOT_A_CONFIG_CODE,
+            "This doesn't look like a Ghostty config".to_string(),
+        )];
+    }
+
+    let mut diagnostics = vec![];
+    let base_dir = paths::document_dir(uri);
+    let line_validators: Vec<&dyn LineValidator> = LINE_VALIDATORS
+        .iter()
+        .copied()
+        .filter(|v| v.enabled(config))
+        .collect();
+
+    for (line_num, parsed) in document.lines().iter().enumerate() {
+        let line = parsed.text.as_str();
+        if let Some(diagnostic) = check_negation_prefix(schema, line_num, line) {
+            diagnostics.push(diagnostic);
+            continue;
+        }
+
+        let Some(assignment) = parsed.assignment() else {
+            diagnostics.extend(check_colon_separator(schema, line_num, line));
+            continue;
+        };
+
+        let check = LineCheck {
+            schema,
+            config,
+            executables,
+            base_dir: base_dir.as_deref(),
+            line_num,
+            line,
+            opt: schema.options.get(assignment.key),
+            // Checked as Ghostty reads it, so `"/usr/bin/env fish"` is a command
+            assignment: assignment.unquoted(),
+        };
+        for validator in &line_validators {
+            diagnostics.extend(validator.check(&check));
+        }
+    }
+
+    let check = DocumentCheck {
+        schema,
+        parsed: document,
+        uri,
+    };
+    for validator in DOCUMENT_VALIDATORS {
+        if validator.enabled(config) {
+            diagnostics.extend(validator.check(&check));
+        }
+    }
+
+    if config.strict {
+        escalate_value_problems(&mut diagnostics);
+    }
+
+    directives::suppress(text, &mut diagnostics);
+    sort_and_dedup(&mut diagnostics);
+
+    diagnostics
+}
+
+/// A `key = value` line, as the line validators see it.
+struct LineCheck<'a> {
+    schema: &'a GhosttySchema,
+    config: &'a Config,
+    executables: &'a ExecutableCache,
+    base_dir: Option<&'a Path>,
+    line_num: usize,
+    line: &'a str,
+    assignment: Assignment<'a>,
+    /// `None` when the key isn't a known option.
+    opt: Option<&'a ConfigOption>,
+}
+
+impl LineCheck<'_> {
+    /// The option, when the key is known and the value isn't empty. An empty
+    /// value resets the option to its default, which is always valid.
+    fn valued_option(&self) -> Option<&ConfigOption> {
+        self.opt.filter(|_| !self.assignment.value.is_empty())
+    }
+
+    /// The range of `len` bytes starting `start` bytes into the value.
+    fn value_span(&self, start: usize, len: usize) -> Range {
+        span(
+            self.line_num,
+            self.line,
+            self.assignment.value_start + start,
+            len,
+        )
+    }
+
+    fn key_range(&self) -> Range {
+        span(
+            self.line_num,
+            self.line,
+            self.assignment.key_start,
+            self.assignment.key.len(),
+        )
+    }
+}
+
+/// The whole document, as the document validators see it.
+struct DocumentCheck<'a> {
+    schema: &'a GhosttySchema,
+    parsed: &'a ParsedDocument,
+    uri: &'a Url,
+}
+
+/// A check run on every `key = value` line.
+trait LineValidator: Sync {
+    /// Whether the settings turn this check on. Most checks always run.
+    fn enabled(&self, _config: &Config) -> bool {
+        true
+    }
+
+    fn check(&self, line: &LineCheck) -> Vec<Diagnostic>;
+}
+
+/// A check that needs the whole document, such as one option depending on
+/// another.
+trait DocumentValidator: Sync {
+    fn enabled(&self, _config: &Config) -> bool {
+        true
+    }
+
+    fn check(&self, document: &DocumentCheck) -> Vec<Diagnostic>;
+}
+
+/// Every line check, run in this order. Add a check by implementing
+/// [`LineValidator`] and listing it here.
+const LINE_VALIDATORS: &[&dyn LineValidator] = &[
+    &TrailingWhitespace,
+    &UnknownKey,
+    &ValueType,
+    &FunctionalColor,
+    &ValueLength,
+    &LineContinuation,
+    &FontVariation,
+    &MultipleEntries,
+    &Keybind,
+    &MissingExecutable,
+    &WindowsPath,
+    &MissingPath,
+];
+
+/// `ghostty +validate-config` isn't one of these: it starts a process, so the
+/// server runs [`ghostty_validate`] separately, off the publishing path.
+const DOCUMENT_VALIDATORS: &[&dyn DocumentValidator] = &[&StyledFonts, &Requirements];
+
+struct TrailingWhitespace;
+
+impl LineValidator for TrailingWhitespace {
+    fn check(&self, line: &LineCheck) -> Vec<Diagnostic> {
+        let content_len = line.line.trim_end().len();
+        if content_len == line.line.len() {
+            return vec![];
+        }
+        let range = span(
+            line.line_num,
+            line.line,
+            content_len,
+            line.line.len() - content_len,
+        );
+        vec![with_fix(
+            diagnostic(
+                range,
+                DiagnosticSeverity::INFORMATION,
+                TRAILING_WHITESPACE_CODE,
+                "Trailing whitespace".to_string(),
+            ),
+            "Trim trailing whitespace",
+            TextEdit::new(range, String::new()),
+        )]
+    }
+}
+
+struct UnknownKey;
+
+impl LineValidator for UnknownKey {
+    fn check(&self, line: &LineCheck) -> Vec<Diagnostic> {
+        if line.opt.is_some() {
+            return vec![];
+        }
+        vec![diagnostic(
+            line.key_range(),
+            DiagnosticSeverity::WARNING,
+            UNKNOWN_KEY_CODE,
+            format!("Unknown configuration key `{}`", line.assignment.key),
+        )]
+    }
+}
+
+/// Values that don't fit the option's type, including enum values that
+/// aren't one of its choices.
+struct ValueType;
+
+impl LineValidator for ValueType {
+    fn check(&self, line: &LineCheck) -> Vec<Diagnostic> {
+        let Some(opt) = line.valued_option() else {
+            return vec![];
+        };
+        let value = line.assignment.value;
+        let value_range = line.value_span(0, value.len());
+        if let Some(replacement) = boolean_alias(opt, value) {
+            return vec![with_fix(
+                invalid_value(
+                    value_range,
+                    format!(
+                        "Ghostty doesn't accept `{}` as a boolean; use `{}`",
+                        value, replacement
+                    ),
+                ),
+                &format!("Replace with `{}`", replacement),
+                TextEdit::new(value_range, replacement.to_string()),
+            )];
+        }
+        if let Some(replacement) = permission_alias(opt, value) {
+            return vec![with_fix(
+                invalid_value(
+                    value_range,
+                    format!(
+                        "`{}` takes `allow`, `deny` or `ask`, not a boolean; use `{}`",
+                        line.assignment.key, replacement
+                    ),
+                ),
+                &format!("Replace with `{}`", replacement),
+                TextEdit::new(value_range, replacement.to_string()),
+            )];
+        }
+        if opt.types().eq(["boolean"]) && PERMISSION_VALUES.contains(&value) {
+            return vec![invalid_value(
+                value_range,
+                format!(
+                    "`{}` is a boolean; `{}` is a choice of `clipboard-read` and `clipboard-write`. Expected `true` or `false`",
+                    line.assignment.key, value
+                ),
+            )];
+        }
+        check_value(line.schema, opt, value, line.config.strict)
+            .map(|message| invalid_value(value_range, message))
+            .into_iter()
+            .collect()
+    }
+}
+
+/// CSS `rgb()` and `hsl()` colours. Ghostty only reads hex and named colours,
+/// so these are always reported, with the hex equivalent as a fix.
+struct FunctionalColor;
+
+impl LineValidator for FunctionalColor {
+    fn check(&self, line: &LineCheck) -> Vec<Diagnostic> {
+        let value = line.assignment.value;
+        let is_color = line
+            .valued_option()
+            .is_some_and(|opt| opt.has_type("color"));
+        let Some(function) = color_function(value).filter(|_| is_color) else {
+            return vec![];
+        };
+        let range = line.value_span(0, value.len());
+        let Some(hex) = functional_color_hex(value) else {
+            return vec![invalid_value(
+                range,
+                format!(
+                    "Ghostty doesn't accept `{}()` colours; use a hex colour such as `#282c34`",
+                    function
+                ),
+            )];
+        };
+        vec![with_fix(
+            invalid_value(
+                range,
+                format!(
+                    "Ghostty doesn't accept `{}()` colours; use the hex colour `{}`",
+                    function, hex
+                ),
+            ),
+            &format!("Replace with `{}`", hex),
+            TextEdit::new(range, hex),
+        )]
+    }
+}
+
+struct ValueLength;
+
+impl LineValidator for ValueLength {
+    fn check(&self, line: &LineCheck) -> Vec<Diagnostic> {
+        let Some(opt) = line.valued_option() else {
+            return vec![];
+        };
+        let value = line.assignment.value;
+        check_length(opt, line.value_span(0, value.len()), value)
+            .into_iter()
+            .collect()
+    }
+}
+
+/// A value ending in a lone `\`. Ghostty reads each line on its own, so the
+/// backslash is kept in the value and the next line isn't joined to it. Paths
+/// are left alone, since a Windows directory can end in one.
+struct LineContinuation;
+
+impl LineValidator for LineContinuation {
+    fn check(&self, line: &LineCheck) -> Vec<Diagnostic> {
+        let Some(opt) = line.valued_option() else {
+            return vec![];
+        };
+        let value = line.assignment.value;
+        let trailing = value.len() - value.trim_end_matches('\\').len();
+        if opt.has_type("path") || trailing.is_multiple_of(2) {
+            return vec![];
+        }
+        let range = line.value_span(value.len() - 1, 1);
+        vec![with_fix(
+            diagnostic(
+                range,
+                DiagnosticSeverity::WARNING,
+                LINE_CONTINUATION_CODE,
+                "Ghostty doesn't continue values onto the next line; this `\\` is part of the value"
+                    .to_string(),
+            ),
+            "Remove the trailing `\\`",
+            TextEdit::new(range, String::new()),
+        )]
+    }
+}
+
+struct FontVariation;
+
+impl LineValidator for FontVariation {
+    fn check(&self, line: &LineCheck) -> Vec<Diagnostic> {
+        let Some(opt) = line.valued_option() else {
+            return vec![];
+        };
+        if opt.option_type != "font-variation" {
+            return vec![];
+        }
+        check_font_variation(line.assignment.value)
+            .map(|(start, len, message)| invalid_value(line.value_span(start, len), message))
+            .into_iter()
+            .collect()
+    }
+}
+
+struct MultipleEntries;
+
+impl LineValidator for MultipleEntries {
+    fn check(&self, line: &LineCheck) -> Vec<Diagnostic> {
+        let Some(opt) = line.valued_option().filter(|opt| opt.repeatable) else {
+            return vec![];
+        };
+        check_multiple_entries(line.schema, opt, line.line_num, line.line, &line.assignment)
+            .into_iter()
+            .collect()
+    }
+}
+
+struct Keybind;
+
+impl LineValidator for Keybind {
+    fn check(&self, line: &LineCheck) -> Vec<Diagnostic> {
+        let is_keybind = line
+            .valued_option()
+            .is_some_and(|opt| opt.option_type == "keybind");
+        let keybind_type = line.schema.types.as_ref().and_then(|t| t.keybind.as_ref());
+        let Some(keybind) = keybind_type.filter(|_| is_keybind) else {
+            return vec![];
+        };
+        check_keybind(
+            line.assignment.value,
+            keybind,
+            line.config.check_modifier_order,
+        )
+        .into_iter()
+        .map(|problem| {
+            let d = diagnostic(
+                line.value_span(problem.start, problem.len),
+                problem.severity,
+                KEYBIND_CODE,
+                problem.message,
+            );
+            match problem.fix {
+                Some(fix) => {
+                    let range = line.value_span(fix.start, fix.len);
+                    with_fix(d, fix.title, TextEdit::new(range, fix.new_text))
+                }
+                None => d,
+            }
+        })
+        .collect()
+    }
+}
+
+struct MissingExecutable;
+
+impl LineValidator for MissingExecutable {
+    fn enabled(&self, config: &Config) -> bool {
+        config.check_executables && !config.safe_mode
+    }
+
+    fn check(&self, line: &LineCheck) -> Vec<Diagnostic> {
+        let is_command = line
+            .valued_option()
+            .is_some_and(|opt| opt.option_type == "command");
+        let Some((offset, program)) = program_token(line.assignment.value).filter(|_| is_command)
+        else {
+            return vec![];
+        };
+        if line.executables.exists(program) {
+            return vec![];
+        }
+        vec![diagnostic(
+            line.value_span(offset, program.len()),
+            DiagnosticSeverity::INFORMATION,
+            MISSING_EXECUTABLE_CODE,
+            format!("`{}` not found on PATH", program),
+        )]
+    }
+}
+
+struct WindowsPath;
+
+impl LineValidator for WindowsPath {
+    fn enabled(&self, _config: &Config) -> bool {
+        !cfg!(windows)
+    }
+
+    fn check(&self, line: &LineCheck) -> Vec<Diagnostic> {
+        if line
+            .valued_option()
+            .is_none_or(|opt| opt.option_type != "path")
+        {
+            return vec![];
+        }
+        check_windows_path(line.line_num, line.line, &line.assignment)
+            .into_iter()
+            .collect()
+    }
+}
+
+struct MissingPath;
+
+impl LineValidator for MissingPath {
+    fn enabled(&self, config: &Config) -> bool {
+        config.check_paths && !config.safe_mode
+    }
+
+    fn check(&self, line: &LineCheck) -> Vec<Diagnostic> {
+        if line
+            .valued_option()
+            .is_none_or(|opt| opt.option_type != "path")
+        {
+            return vec![];
+        }
+        let value = line.assignment.value;
+        check_path(value, line.base_dir)
+            .map(|message| {
+                diagnostic(
+                    line.value_span(0, value.len()),
+                    DiagnosticSeverity::WARNING,
+                    MISSING_PATH_CODE,
+                    message,
+                )
+            })
+            .into_iter()
+            .collect()
+    }
+}
+
+struct StyledFonts;
+
+impl DocumentValidator for StyledFonts {
+    fn check(&self, document: &DocumentCheck) -> Vec<Diagnostic> {
+        let mut has_base_font = false;
+        let mut styled_fonts: Vec<(Range, &str)> = vec![];
+        for (line_num, line, assignment) in document.parsed.assignments() {
+            if assignment.key == "font-family" {
+                has_base_font = true;
+            } else if FONT_STYLE_KEYS.contains(&assignment.key) {
+                let range = span(line_num, line, assignment.key_start, assignment.key.len());
+                styled_fonts.push((range, assignment.key));
+            }
+        }
+        if has_base_font {
+            return vec![];
+        }
+        check_styled_fonts(document.uri, &styled_fonts)
+    }
+}
+
+struct Requirements;
+
+impl DocumentValidator for Requirements {
+    fn check(&self, document: &DocumentCheck) -> Vec<Diagnostic> {
+        check_requirements(document.schema, document.parsed)
+    }
+}
+
+/// Orders diagnostics by position, then severity, and drops exact repeats, so
+/// the editor shows the same list on every pass.
+fn sort_and_dedup(diagnostics: &mut Vec<Diagnostic>) {
+    diagnostics.sort_by(|a, b| {
+        let key = |d: &Diagnostic| (d.range.start, d.severity, d.range.end);
+        key(a).cmp(&key(b)).then_with(|| a.message.cmp(&b.message))
+    });
+    diagnostics.dedup();
+}
+
+/// Strict mode: every value that doesn't fit its option's type is an error.
+fn escalate_value_problems(diagnostics: &mut [Diagnostic]) {
+    let code = NumberOrString::String(INVALID_VALUE_CODE.to_string());
+    for d in diagnostics
+        .iter_mut()
+        .filter(|d| d.code.as_ref() == Some(&code))
+    {
+        d.severity = Some(DiagnosticSeverity::ERROR);
+    }
+}
+
+/// A bold or italic family without a base `font-family` usually means the base
+/// was forgotten: regular text keeps Ghostty's default font.
+fn check_styled_fonts(uri: &Url, styled_fonts: &[(Range, &str)]) -> Vec<Diagnostic> {
+    styled_fonts
+        .iter()
+        .map(|(range, key)| {
+            let mut d = diagnostic(
+                *range,
+                DiagnosticSeverity::INFORMATION,
+                FONT_FAMILY_CODE,
+                format!(
+                    "`{}` is set but `font-family` isn't, so regular text uses the default font",
+                    key
+                ),
+            );
+            let related: Vec<DiagnosticRelatedInformation> = styled_fonts
+                .iter()
+                .filter(|(other, _)| other != range)
+                .map(|(other, other_key)| DiagnosticRelatedInformation {
+                    location: Location::new(uri.clone(), *other),
+                    message: format!("`{}` is also set without a base family", other_key),
+                })
+                .collect();
+            if !related.is_empty() {
+                d.related_information = Some(related);
+            }
+            d
+        })
+        .collect()
+}
+
+/// A copy of the document in the temp directory, removed when dropped.
+struct TempConfig(PathBuf);
+
+impl TempConfig {
+    async fn write(text: &str) -> Option<Self> {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        let name = format!(
+            "ghostty-lsp-{}-{}.conf",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = std::env::temp_dir().join(name);
+        tokio::fs::write(&path, text).await.ok()?;
+        Some(Self(path))
+    }
+}
+
+impl Drop for TempConfig {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Whether `ghostty +validate-config` reported the diagnostic.
+pub fn is_from_ghostty(diagnostic: &Diagnostic) -> bool {
+    matches!(&diagnostic.code, Some(NumberOrString::String(code)) if code == GHOSTTY_CODE)
+}
+
+/// Runs `ghostty +validate-config` on a temporary copy of the document and
+/// turns each `path:line:key: message` it prints into a diagnostic on that
+/// line, honouring comment directives. `None` when `ghostty` can't be run or
+/// doesn't finish within `timeout`. Relative paths in the document resolve
+/// against the temp directory, so `config-file` includes may be reported
+/// missing.
+pub async fn ghostty_validate(
+    document: &ParsedDocument,
+    timeout: Duration,
+) -> Option<Vec<Diagnostic>> {
+    let file = TempConfig::write(document.text()).await?;
+    let command = tokio::process::Command::new("ghostty")
+        .arg("+validate-config")
+        .arg(format!("--config-file={}", file.0.display()))
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(timeout, command).await.ok()?.ok()?;
+    if output.status.success() {
+        return Some(vec![]);
+    }
+
+    let prefix = format!("{}:", file.0.display());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let mut diagnostics = stdout
+        .lines()
+        .chain(stderr.lines())
+        .filter_map(|line| line.strip_prefix(&prefix))
+        .filter_map(|located| {
+            let (line_num, message) = located.split_once(':')?;
+            // Ghostty counts lines from one
+            let line_num = line_num.trim().parse::<usize>().ok()?.checked_sub(1)?;
+            let message = message.trim();
+            let parsed = document.line(line_num);
+            let range = match parsed.and_then(|line| Some((line, line.assignment()?))) {
+                Some((line, assignment)) => span(
+                    line_num,
+                    &line.text,
+                    assignment.key_start,
+                    assignment.key.len(),
+                ),
+                // A line past the end means Ghostty read something else
+                None => parsed.map_or(Range::default(), |line| {
+                    span(line_num, &line.text, 0, line.text.len())
+                }),
+            };
+            Some(diagnostic(
+                range,
+                DiagnosticSeverity::ERROR,
+                GHOSTTY_CODE,
+                format!("Ghostty: {}", message),
+            ))
+        })
+        .collect();
+    directives::suppress(document.text(), &mut diagnostics);
+    sort_and_dedup(&mut diagnostics);
+    Some(diagnostics)
+}
+
+/// Options whose schema `requires` entry isn't met by the value the file
+/// gives the option they depend on. An empty value resets that option, so it
+/// counts as unset.
+fn check_requirements(schema: &GhosttySchema, document: &ParsedDocument) -> Vec<Diagnostic> {
+    let assignments: Vec<(usize, &str, Assignment)> = document
+        .assignments()
+        .map(|(line_num, line, assignment)| (line_num, line, assignment.unquoted()))
+        .collect();
+    let mut values: HashMap<&str, &str> = HashMap::new();
+    for (_, _, assignment) in &assignments {
+        if assignment.value.is_empty() {
+            values.remove(assignment.key);
+        } else {
+            values.insert(assignment.key, assignment.value);
+        }
+    }
+
+    assignments
+        .iter()
+        .filter(|(_, _, assignment)| !assignment.value.is_empty())
+        .filter_map(|(line_num, line, assignment)| {
+            let requires = schema.options.get(assignment.key)?.requires.as_ref()?;
+            let value = values.get(requires.key.as_str()).copied();
+            if requirement_met(requires, value) {
+                return None;
+            }
+            Some(diagnostic(
+                span(*line_num, line, assignment.key_start, assignment.key.len()),
+                DiagnosticSeverity::INFORMATION,
+                NO_EFFECT_CODE,
+                format!(
+                    "`{}` has no effect unless {}",
+                    assignment.key,
+                    describe_requirement(requires)
+                ),
+            ))
+        })
+        .collect()
+}
+
+fn requirement_met(requires: &Requirement, value: Option<&str>) -> bool {
+    let Some(value) = value else {
+        return false;
+    };
+    if let Some(one_of) = &requires.one_of {
+        return one_of.iter().any(|v| v == value);
+    }
+    if let Some(below) = requires.below {
+        return value.parse::<f64>().is_ok_and(|n| n < below);
+    }
+    true
+}
+
+fn describe_requirement(requires: &Requirement) -> String {
+    if let Some(one_of) = &requires.one_of {
+        return format!("`{}` is `{}`", requires.key, one_of.join("` or `"));
+    }
+    if let Some(below) = requires.below {
+        return format!("`{}` < {}", requires.key, below);
+    }
+    format!("`{}` is set", requires.key)
+}
+
+/// Catches YAML-style `key: value` lines. Only lines without any `=` get here,
+/// so keybind values such as `global:ctrl+a=...` are never mistaken for one,
+/// and the key must be a known option so stray text like URLs is left alone.
+fn check_colon_separator(
+    schema: &GhosttySchema,
+    line_num: usize,
+    line: &str,
+) -> Option<Diagnostic> {
+    let trimmed = line.trim_start();
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+        return None;
+    }
+
+    let colon = line.find(':')?;
+    let key = line[..colon].trim();
+    if !schema.options.contains_key(key) {
+        return None;
+    }
+
+    // Replace the colon and the whitespace around it
+    let key_end = line[..colon].trim_end().len();
+    let value_start = colon + 1 + (line[colon + 1..].len() - line[colon + 1..].trim_start().len());
+    Some(with_fix(
+        diagnostic(
+            span(line_num, line, colon, 1),
+            DiagnosticSeverity::ERROR,
+            SYNTAX_CODE,
+            "Ghostty uses `=`, not `:`".to_string(),
+        ),
+        "Replace `:` with `=`",
+        TextEdit::new(
+            span(line_num, line, key_end, value_start - key_end),
+            " = ".to_string(),
+        ),
+    ))
+}
+
+/// Ghostty has no `!key` shorthand for negating an option; a leading `!` just
+/// makes the key unknown, so point at it with a more helpful message.
+fn check_negation_prefix(
+    schema: &GhosttySchema,
+    line_num: usize,
+    line: &str,
+) -> Option<Diagnostic> {
+    let trimmed = line.trim_start();
+    let rest = trimmed.strip_prefix('!')?;
+    let start = line.len() - trimmed.len();
+
+    let key = rest.split('=').next().unwrap_or("").trim();
+    let message = match schema.options.get(key) {
+        Some(opt) if opt.has_type("boolean") => format!(
+            "Ghostty doesn't support `!` negation; use `{} = false` instead",
+            key
+        ),
+        _ => "Ghostty doesn't support a `!` prefix on configuration keys".to_string(),
+    };
+
+    Some(diagnostic(
+        span(line_num, line, start, 1),
+        DiagnosticSeverity::WARNING,
+        SYNTAX_CODE,
+        message,
+    ))
+}
+
+/// Flags values longer than the schema's `maxLength` for the option. This
+/// isn't a type problem, so strict mode leaves it a warning.
+fn check_length(opt: &ConfigOption, range: Range, value: &str) -> Option<Diagnostic> {
+    let max = opt.max_length?;
+    let len = value.chars().count();
+    (len > max).then(|| {
+        diagnostic(
+            range,
+            DiagnosticSeverity::WARNING,
+            VALUE_TOO_LONG_CODE,
+            format!(
+                "Value is {} characters long; this option is rarely longer than {}",
+                len, max
+            ),
+        )
+    })
+}
+
+/// Users often write `yes`/`no` or `on`/`off`, which Ghostty's boolean parser
+/// rejects. Returns the spelling it does accept.
+fn boolean_alias(opt: &ConfigOption, value: &str) -> Option<&'static str> {
+    if !opt.has_type("boolean") {
+        return None;
+    }
+    match value.to_ascii_lowercase().as_str() {
+        "yes" | "on" => Some("true"),
+        "no" | "off" => Some("false"),
+        _ => None,
+    }
+}
+
+/// A boolean given where `allow`/`deny`/`ask` is expected. Returns the choice
+/// it most likely means.
+fn permission_alias(opt: &ConfigOption, value: &str) -> Option<&'static str> {
+    let choices = opt.enum_values.as_deref()?;
+    if !PERMISSION_VALUES
+        .iter()
+        .all(|v| choices.iter().any(|c| c == v))
+    {
+        return None;
+    }
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" => Some("allow"),
+        "false" | "no" | "off" => Some("deny"),
+        _ => None,
+    }
+}
+
+fn check_value(
+    schema: &GhosttySchema,
+    opt: &ConfigOption,
+    value: &str,
+    strict: bool,
+) -> Option<String> {
+    let types: Vec<&str> = opt.types().collect();
+    match types[..] {
+        [option_type] => check_type(schema, opt, option_type, value, strict),
+        _ => check_union(schema, opt, &types, value, strict),
+    }
+}
+
+/// A union value is valid if it fits any member. It's only reported outside
+/// strict mode when a member would be checked on its own.
+fn check_union(
+    schema: &GhosttySchema,
+    opt: &ConfigOption,
+    types: &[&str],
+    value: &str,
+    strict: bool,
+) -> Option<String> {
+    if !strict && !types.iter().any(|t| always_checked(opt, t)) {
+        return None;
+    }
+    if types
+        .iter()
+        .any(|t| check_type(schema, opt, t, value, true).is_none())
+    {
+        return None;
+    }
+    let expected: Vec<String> = types.iter().map(|t| describe_type(opt, t)).collect();
+    Some(format!(
+        "Invalid value `{}`. Expected {}",
+        value,
+        expected.join(" or ")
+    ))
+}
+
+fn always_checked(opt: &ConfigOption, option_type: &str) -> bool {
+    match option_type {
+        "enum" | "flags" | "regex" | "keyvalue-list" => true,
+        "number" => has_number_constraints(opt),
+        _ => false,
+    }
+}
+
+/// What a value of `option_type` looks like, for union error messages.
+fn describe_type(opt: &ConfigOption, option_type: &str) -> String {
+    match option_type {
+        "boolean" => "`true` or `false`".to_string(),
+        "number" => describe_number(opt),
+        "percentage" => "a number or percentage".to_string(),
+        "duration" => "a duration such as `500ms`".to_string(),
+        "color" => "a colour".to_string(),
+        "keyvalue-list" => "`name=value` pairs".to_string(),
+        "enum" => format!(
+            "one of: {}",
+            opt.enum_values.as_deref().unwrap_or_default().join(", ")
+        ),
+        other => format!("a {}", other),
+    }
+}
+
+/// Enum, regex and key/value list values are always checked; the remaining
+/// type shapes are only checked in strict mode.
+fn check_type(
+    schema: &GhosttySchema,
+    opt: &ConfigOption,
+    option_type: &str,
+    value: &str,
+    strict: bool,
+) -> Option<String> {
+    match option_type {
+        "enum" => check_enum(opt, value),
+        "flags" => check_flags(opt, value),
+        "regex" => check_regex(value),
+        "number" if has_number_constraints(opt) => check_number(opt, value),
+        "keyvalue-list" => check_keyvalue_list(value),
+        _ if !strict => None,
+        "boolean" => (!BOOLEAN_VALUES.contains(&value))
+            .then(|| format!("Invalid value `{}`. Expected `true` or `false`", value)),
+        "color" => check_color(schema, value),
+        "number" => parse_number(value)
+            .is_none()
+            .then(|| format!("Invalid value `{}`. Expected a number", value)),
+        "percentage" => value
+            .strip_suffix('%')
+            .unwrap_or(value)
+            .parse::<f64>()
+            .is_err()
+            .then(|| format!("Invalid value `{}`. Expected a number or percentage", value)),
+        "duration" => (!is_duration(value)).then(|| {
+            format!(
+                "Invalid value `{}`. Expected a duration such as `500ms` or `1h30m`",
+                value
+            )
+        }),
+        _ => None,
+    }
+}
+
+/// Each comma-separated segment must be a single `name=value` pair.
+fn check_keyvalue_list(value: &str) -> Option<String> {
+    let malformed: Vec<&str> = value
+        .split(',')
+        .map(str::trim)
+        .filter(|pair| {
+            pair.matches('=').count() != 1 || pair.split('=').next().is_some_and(str::is_empty)
+        })
+        .collect();
+    if malformed.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "Malformed pair{} `{}`. Expected comma-separated `name=value` pairs",
+        if malformed.len() == 1 { "" } else { "s" },
+        malformed.join("`, `")
+    ))
+}
+
+fn has_number_constraints(opt: &ConfigOption) -> bool {
+    opt.minimum.is_some() || opt.maximum.is_some() || opt.integer || opt.unit.is_some()
+}
+
+/// Numbers whose schema entry gives a range, unit or integer constraint are
+/// always checked, since a value outside them is never what was meant. Without
+/// `integer` an option takes decimals, such as `font-size = 12.5`.
+fn check_number(opt: &ConfigOption, value: &str) -> Option<String> {
+    let fits = parse_number(value).is_some_and(|n| {
+        (!opt.integer || n.fract() == 0.0)
+            && opt.minimum.is_none_or(|min| n >= min)
+            && opt.maximum.is_none_or(|max| n <= max)
+    });
+    if fits {
+        return None;
+    }
+    Some(format!(
+        "Invalid value `{}`. Expected {}",
+        value,
+        describe_number(opt)
+    ))
+}
+
+/// `f64` parsing also accepts `inf` and `NaN`, which no option takes.
+fn parse_number(value: &str) -> Option<f64> {
+    value.parse::<f64>().ok().filter(|n| n.is_finite())
+}
+
+fn describe_number(opt: &ConfigOption) -> String {
+    let mut expected = if opt.integer {
+        "a whole number"
+    } else {
+        "a number"
+    }
+    .to_string();
+    if let Some(unit) = &opt.unit {
+        expected.push_str(&format!(" of {}", unit));
+    }
+    match (opt.minimum, opt.maximum) {
+        (Some(min), Some(max)) => expected.push_str(&format!(" between {} and {}", min, max)),
+        (Some(min), None) => expected.push_str(&format!(", at least {}", min)),
+        (None, Some(max)) => expected.push_str(&format!(", at most {}", max)),
+        (None, None) => {}
+    }
+    expected
+}
+
+/// The CSS colour function a value is written with, such as `rgb`.
+fn color_function(value: &str) -> Option<&str> {
+    let (name, _) = value.split_once('(')?;
+    let name = name.trim();
+    ["rgb", "rgba", "hsl", "hsla"]
+        .iter()
+        .any(|f| f.eq_ignore_ascii_case(name))
+        .then_some(name)
+}
+
+/// `rgb(255, 128, 0)` or `hsl(30deg 100% 50%)` as `#ff8000`. Any alpha is
+/// dropped, since Ghostty's colours have none. `None` when the arguments
+/// aren't three numbers.
+fn functional_color_hex(value: &str) -> Option<String> {
+    let function = color_function(value)?.to_ascii_lowercase();
+    let (_, args) = value.split_once('(')?;
+    let args = args.trim_end().strip_suffix(')')?;
+    let parts: Vec<&str> = args
+        .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+        .filter(|p| !p.is_empty())
+        .collect();
+    if !(3..=4).contains(&parts.len()) {
+        return None;
+    }
+
+    // A bare number or a percentage, with the percentage as a fraction
+    let number = |part: &str| -> Option<(f64, bool)> {
+        let (digits, percent) = match part.strip_suffix('%') {
+            Some(digits) => (digits, true),
+            None => (part, false),
+        };
+        let n = digits.parse::<f64>().ok().filter(|n| n.is_finite())?;
+        Some(if percent {
+            (n / 100.0, true)
+        } else {
+            (n, false)
+        })
+    };
+
+    let rgb = if function.starts_with("rgb") {
+        let mut channels = [0.0; 3];
+        for (channel, part) in channels.iter_mut().zip(&parts) {
+            let (n, percent) = number(part)?;
+            *channel = if percent { n * 255.0 } else { n };
+        }
+        channels
+    } else {
+        let hue = number(parts[0].strip_suffix("deg").unwrap_or(parts[0]))?.0;
+        let saturation = number(parts[1])?.0.clamp(0.0, 1.0);
+        let lightness = number(parts[2])?.0.clamp(0.0, 1.0);
+        hsl_to_rgb(hue, saturation, lightness)
+    };
+
+    let [r, g, b] = rgb.map(|c| c.round().clamp(0.0, 255.0) as u8);
+    Some(format!("#{:02x}{:02x}{:02x}", r, g, b))
+}
+
+/// Channels from 0 to 255 for a hue in degrees and saturation and lightness
+/// from 0 to 1.
+fn hsl_to_rgb(hue: f64, saturation: f64, lightness: f64) -> [f64; 3] {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let h = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    [r, g, b].map(|c| (c + m) * 255.0)
+}
+
+/// Hex colours and the schema's named values are checked exactly. Other plain
+/// words are let through because Ghostty accepts every X11 colour name.
+fn check_color(schema: &GhosttySchema, value: &str) -> Option<String> {
+    let named = schema
+        .types
+        .as_ref()
+        .and_then(|t| t.color.as_ref())
+        .and_then(|c| c.named_values.as_deref())
+        .unwrap_or_default();
+    // Reported with a fix by the functional colour check instead
+    if named.iter().any(|n| n == value) || color_function(value).is_some() {
+        return None;
+    }
+
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    let is_hex = matches!(hex.len(), 3 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit());
+    let is_word =
+        !value.starts_with('#') && value.chars().all(|c| c.is_ascii_alphabetic() || c == ' ');
+    if is_hex || is_word {
+        return None;
+    }
+    Some(format!(
+        "Invalid colour `{}`. Expected a hex colour such as `#282c34` or a colour name",
+        value
+    ))
+}
+
+/// One or more `<number><unit>` groups, optionally separated by spaces. A bare
+/// `0` is also accepted.
+fn is_duration(value: &str) -> bool {
+    if value == "0" {
+        return true;
+    }
+    let compact: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut rest = compact.as_str();
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        if digits == 0 || rest[..digits].parse::<f64>().is_err() {
+            return false;
+        }
+        rest = &rest[digits..];
+        let Some(unit) = DURATION_UNITS.iter().find(|u| rest.starts_with(*u)) else {
+            return false;
+        };
+        rest = &rest[unit.len()..];
+    }
+    true
+}
+
+/// Reports a `path` value that doesn't exist. Optional (`?`) paths and paths
+/// that still reference an unset variable after expansion aren't checked.
+fn check_path(value: &str, base_dir: Option<&Path>) -> Option<String> {
+    if value.starts_with('?') {
+        return None;
+    }
+    let raw = value.trim_matches('"');
+    let path = paths::resolve(raw, base_dir)?;
+    if path.to_string_lossy().contains('$') || path.exists() {
+        return None;
+    }
+    Some(format!("File not found: `{}`", path.display()))
+}
+
+/// A path pasted from Windows won't resolve on Linux or macOS. Only values
+/// that start like a Windows path are flagged, since a backslash elsewhere may
+/// be an escape.
+fn check_windows_path(line_num: usize, line: &str, assignment: &Assignment) -> Option<Diagnostic> {
+    let offset = assignment.value.len() - assignment.value.trim_start_matches(['?', '"']).len();
+    let raw = &assignment.value[offset..];
+    let bytes = raw.as_bytes();
+    let has_drive =
+        bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && bytes[2] == b'\\';
+    let relative_start = ["\\\\", "~\\", ".\\", "..\\"]
+        .iter()
+        .any(|p| raw.starts_with(p));
+    if !has_drive && !relative_start {
+        return None;
+    }
+
+    let path = raw.trim_end_matches('"');
+    let range = span(line_num, line, assignment.value_start + offset, path.len());
+    let d = diagnostic(
+        range,
+        DiagnosticSeverity::INFORMATION,
+        WINDOWS_PATH_CODE,
+        "This looks like a Windows path; Ghostty on this system expects `/` separators".to_string(),
+    );
+    // A drive letter won't resolve here whatever the separators
+    if has_drive {
+        return Some(d);
+    }
+    let fixed = path.replace('\\', "/");
+    Some(with_fix(
+        d,
+        "Use forward slashes",
+        TextEdit::new(range, fixed),
+    ))
+}
+
+/// Repeatable keys accumulate one entry per line, so a comma-separated list of
+/// entries (`palette = 0=#000000,1=#ff0000`) is read as a single malformed
+/// entry. Only reported when every part is a complete entry on its own; keys
+/// whose values can legitimately contain commas are never checked.
+fn check_multiple_entries(
+    schema: &GhosttySchema,
+    opt: &ConfigOption,
+    line_num: usize,
+    line: &str,
+    assignment: &Assignment,
+) -> Option<Diagnostic> {
+    let parts: Vec<&str> = assignment.value.split(',').map(str::trim).collect();
+    if parts.len() < 2
+        || !parts
+            .iter()
+            .all(|part| is_entry(schema, opt, assignment.key, part))
+    {
+        return None;
+    }
+
+    let indent = &line[..assignment.key_start];
+    let lines: Vec<String> = parts
+        .iter()
+        .map(|part| format!("{}{} = {}", indent, assignment.key, part))
+        .collect();
+    let value_end = assignment.value_start + assignment.value.len();
+    let d = diagnostic(
+        span(
+            line_num,
+            line,
+            assignment.value_start,
+            assignment.value.len(),
+        ),
+        DiagnosticSeverity::WARNING,
+        MULTIPLE_ENTRIES_CODE,
+        format!(
+            "`{}` takes one entry per line; these {} entries need a line each",
+            assignment.key,
+            parts.len()
+        ),
+    );
+    Some(with_fix(
+        d,
+        "Split into separate lines",
+        TextEdit::new(span(line_num, line, 0, value_end), lines.join("\n")),
+    ))
+}
+
+/// Whether `part` is a whole entry for a repeatable key: `N=colour` for
+/// `palette`, `trigger=action` with a known action for keybinds.
+fn is_entry(schema: &GhosttySchema, opt: &ConfigOption, key: &str, part: &str) -> bool {
+    if key == "palette" {
+        return part.split_once('=').is_some_and(|(index, colour)| {
+            index.trim().parse::<u8>().is_ok() && !colour.trim().is_empty()
+        });
+    }
+    if opt.option_type != "keybind" {
+        return false;
+    }
+    let Some(keybind) = schema.types.as_ref().and_then(|t| t.keybind.as_ref()) else {
+        return false;
+    };
+    let prefixes = keybind.prefixes.as_deref().unwrap_or_default();
+    let (_, rest) = strip_keybind_prefixes(part, prefixes);
+    let Some((trigger, action)) = split_keybind(rest) else {
+        return false;
+    };
+    let name = action.split(':').next().unwrap_or_default();
+    !trigger.contains(char::is_whitespace) && keybind.actions.iter().flatten().any(|a| a == name)
+}
+
+/// Ghostty uses Oniguruma, which supports look-around and backreferences that
+/// the `regex` crate rejects, so those errors aren't reported.
+fn check_regex(value: &str) -> Option<String> {
+    let regex::Error::Syntax(message) = regex::Regex::new(value).err()? else {
+        return None;
+    };
+    if message.contains("look-around") || message.contains("backreferences") {
+        return None;
+    }
+    // The message is a multi-line rendering of the pattern; keep the reason
+    let reason = message
+        .lines()
+        .rev()
+        .find_map(|line| line.strip_prefix("error: "))
+        .unwrap_or(&message);
+    Some(format!("Invalid regex: {}", reason))
+}
+
+fn check_enum(opt: &ConfigOption, value: &str) -> Option<String> {
+    let enum_values = opt.enum_values.as_ref()?;
+    if enum_values.iter().any(|v| v == value) {
+        return None;
+    }
+    Some(format!(
+        "Invalid value `{}`. Expected one of: {}",
+        value,
+        enum_values.join(", ")
+    ))
+}
+
+/// Checks a `tag=value` axis setting, returning the byte range of the part at
+/// fault. Fonts can define their own axes, so any four-character tag is fine.
+fn check_font_variation(value: &str) -> Option<(usize, usize, String)> {
+    let Some((tag, axis_value)) = value.split_once('=') else {
+        return Some((
+            0,
+            value.len(),
+            format!("Expected `tag=value` such as `wght=500`, got `{}`", value),
+        ));
+    };
+
+    let tag_trimmed = tag.trim();
+    if tag_trimmed.chars().count() != 4 || !tag_trimmed.chars().all(|c| c.is_ascii_graphic()) {
+        let start = tag.len() - tag.trim_start().len();
+        return Some((
+            start,
+            tag_trimmed.len(),
+            format!(
+                "Axis tag `{}` must be four characters, such as `wght` or `slnt`",
+                tag_trimmed
+            ),
+        ));
+    }
+
+    let number = axis_value.trim();
+    if number.is_empty() {
+        return Some((
+            0,
+            value.len(),
+            format!(
+                "Axis `{}` needs a value, such as `{}=500`",
+                tag_trimmed, tag_trimmed
+            ),
+        ));
+    }
+    if number.parse::<f64>().is_err() {
+        let start = tag.len() + 1 + (axis_value.len() - axis_value.trim_start().len());
+        return Some((
+            start,
+            number.len(),
+            format!("Axis value `{}` must be a number", number),
+        ));
+    }
+
+    None
+}
+
+/// Each comma-separated entry must be a known feature, prefixed with `no-`
+/// if the option is negatable, and no feature may be both enabled and
+/// disabled. `true` and `false` switch every feature at once.
+fn check_flags(opt: &ConfigOption, value: &str) -> Option<String> {
+    let flags = opt.flags.as_ref()?;
+    let entries: Vec<&str> = value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty() && !matches!(*entry, "true" | "false"))
+        .collect();
+    let name = |entry: &str| {
+        if opt.negatable {
+            flag_name(entry).to_string()
+        } else {
+            entry.to_string()
+        }
+    };
+
+    let unknown: Vec<&str> = entries
+        .iter()
+        .copied()
+        .filter(|entry| !flags.contains(&name(entry)))
+        .collect();
+    if !unknown.is_empty() {
+        return Some(format!(
+            "Unknown feature{} `{}`. Expected one of: {}{}",
+            if unknown.len() == 1 { "" } else { "s" },
+            unknown.join("`, `"),
+            flags.join(", "),
+            if opt.negatable {
+                " (each optionally prefixed with `no-`)"
+            } else {
+                ""
+            }
+        ));
+    }
+
+    let conflicting: Vec<&str> = entries
+        .iter()
+        .copied()
+        .filter(|entry| !entry.starts_with("no-"))
+        .filter(|entry| entries.contains(&format!("no-{}", entry).as_str()))
+        .collect();
+    if conflicting.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "`{}` {} both enabled and disabled in the same list",
+        conflicting.join("`, `"),
+        if conflicting.len() == 1 { "is" } else { "are" }
+    ))
+}
+
+/// Problem counts across documents, sent to the client in the
+/// `$/ghostty/diagnosticsSummary` notification.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub errors: usize,
+    pub warnings: usize,
+    pub information: usize,
+    /// Documents with at least one diagnostic.
+    pub documents: usize,
+}
+
+impl Summary {
+    pub fn add(&mut self, diagnostics: &[Diagnostic]) {
+        for diagnostic in diagnostics {
+            match diagnostic.severity {
+                Some(DiagnosticSeverity::ERROR) => self.errors += 1,
+                Some(DiagnosticSeverity::WARNING) => self.warnings += 1,
+                Some(DiagnosticSeverity::INFORMATION) => self.information += 1,
+                _ => {}
+            }
+        }
+        if !diagnostics.is_empty() {
+            self.documents += 1;
+        }
+    }
+}
+
+/// Human-readable health summary such as "3 errors, 1 warning".
+pub fn summarize(diagnostics: &[Diagnostic]) -> String {
+    let count = |severity| {
+        diagnostics
+            .iter()
+            .filter(|d| d.severity == Some(severity))
+            .count()
+    };
+
+    let parts: Vec<String> = [
+        (count(DiagnosticSeverity::ERROR), "error", "errors"),
+        (count(DiagnosticSeverity::WARNING), "warning", "warnings"),
+        (count(DiagnosticSeverity::INFORMATION), "info", "infos"),
+    ]
+    .into_iter()
+    .filter(|(n, _, _)| *n > 0)
+    .map(|(n, one, many)| format!("{} {}", n, if n == 1 { one } else { many }))
+    .collect();
+
+    if parts.is_empty() {
+        "No problems".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::byte_offset;
+
+    fn lint(text: &str) -> Vec<Diagnostic> {
+        crate::lint(&GhosttySchema::load(), text)
+    }
+
+    fn lint_with(config: &Config, text: &str) -> Vec<Diagnostic> {
+        validate_document(
+            &GhosttySchema::load(),
+            &ParsedDocument::new(text),
+            config,
+            &ExecutableCache::default(),
+            &Url::parse("untitled:ghostty-config").unwrap(),
+        )
+    }
+
+    fn strict() -> Config {
+        Config {
+            strict: true,
+            ..Config::default()
+        }
+    }
+
+    /// The codes reported for `text`, in order.
+    fn codes(text: &str) -> Vec<String> {
+        lint(text)
+            .into_iter()
+            .filter_map(|d| match d.code {
+                Some(NumberOrString::String(code)) => Some(code),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The one-line `text` after applying the fix of its `code` diagnostic.
+    fn fixed(text: &str, code: &str) -> String {
+        let diagnostic = lint(text)
+            .into_iter()
+            .find(|d| d.code == Some(NumberOrString::String(code.to_string())))
+            .unwrap_or_else(|| panic!("no {} diagnostic", code));
+        let fix = Fix::from_diagnostic(&diagnostic).expect("diagnostic has a fix");
+        let range = fix.edit.range;
+        assert_eq!(range.start.line, range.end.line);
+        let start = byte_offset(text, range.start.character);
+        let end = byte_offset(text, range.end.character);
+        format!("{}{}{}", &text[..start], fix.edit.new_text, &text[end..])
+    }
+
+    #[test]
+    fn equals_inside_keybind_values_is_accepted() {
+        assert_eq!(codes("keybind = ctrl+a=text:a=b\n"), Vec::<String>::new());
+        assert_eq!(
+            codes("keybind = ctrl+==increase_font_size:1\n"),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn regex_values_must_compile() {
+        assert_eq!(codes("link = https?://[^\\s]+\n"), Vec::<String>::new());
+        assert_eq!(codes("link = (unclosed\n"), [INVALID_VALUE_CODE]);
+    }
+
+    #[test]
+    fn colon_separator_is_an_error_with_a_fix() {
+        let diagnostics = lint("font-size: 14");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(fixed("font-size: 14", SYNTAX_CODE), "font-size = 14");
+        assert_eq!(fixed("font-size :  14", SYNTAX_CODE), "font-size = 14");
+    }
+
+    #[test]
+    fn colon_in_unknown_text_is_not_a_separator() {
+        assert!(!codes("https://example.com").contains(&SYNTAX_CODE.to_string()));
+    }
+
+    #[test]
+    fn yes_and_no_booleans_are_fixed_to_true_and_false() {
+        assert_eq!(
+            fixed("maximize = yes", INVALID_VALUE_CODE),
+            "maximize = true"
+        );
+        assert_eq!(
+            fixed("maximize = Off", INVALID_VALUE_CODE),
+            "maximize = false"
+        );
+    }
+
+    #[test]
+    fn booleans_ghostty_accepts_are_not_flagged() {
+        for value in ["true", "false", "1", "0", "t", "F"] {
+            assert_eq!(
+                codes(&format!("maximize = {}", value)),
+                Vec::<String>::new()
+            );
+        }
+    }
+
+    #[test]
+    fn disable_directive_silences_the_next_line() {
+        let diagnostics = lint("# ghostty-lsp: disable\nbogus = 1\nbogus = 2\n");
+        let lines: Vec<u32> = diagnostics.iter().map(|d| d.range.start.line).collect();
+        assert_eq!(lines, [2]);
+    }
+
+    #[test]
+    fn disable_directive_with_codes_silences_only_those() {
+        let text = "# ghostty-lsp: disable invalidValue\nbogus = 1\n";
+        assert_eq!(codes(text), [UNKNOWN_KEY_CODE]);
+        let text = "# ghostty-lsp: disable unknown-key\nbogus = 1\n";
+        assert_eq!(codes(text), Vec::<String>::new());
+    }
+
+    #[test]
+    fn disable_file_directive_silences_every_line() {
+        let text =
+            "bogus = 1\n# ghostty-lsp: disable-file unknown-key\nfont-size = big\nother = 2\n";
+        assert_eq!(codes(text), [INVALID_VALUE_CODE]);
+    }
+
+    #[test]
+    fn unknown_shell_integration_feature_is_reported() {
+        assert_eq!(
+            codes("shell-integration-features = cursor,sudo\n"),
+            Vec::<String>::new()
+        );
+        let diagnostics = lint("shell-integration-features = cursor,sudoo\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("Unknown feature `sudoo`"));
+    }
+
+    #[test]
+    fn font_variation_needs_a_four_character_tag_and_a_number() {
+        assert_eq!(codes("font-variation = wght=500\n"), Vec::<String>::new());
+        for text in [
+            "font-variation = wght\n",
+            "font-variation = weight=500\n",
+            "font-variation = wght=\n",
+            "font-variation = wght=bold\n",
+        ] {
+            assert_eq!(codes(text), [INVALID_VALUE_CODE], "{}", text);
+        }
+    }
+
+    #[test]
+    fn font_variation_points_at_the_bad_part() {
+        let diagnostics = lint("font-variation = wght=bold");
+        assert_eq!(diagnostics[0].range.start.character, 22);
+        assert_eq!(diagnostics[0].range.end.character, 26);
+    }
+
+    #[test]
+    fn diagnostics_are_sorted_by_position_then_severity() {
+        let at =
+            |line, character| Range::new(Position::new(line, character), Position::new(line, 9));
+        let warning = |range, message: &str| {
+            diagnostic(
+                range,
+                DiagnosticSeverity::WARNING,
+                INVALID_VALUE_CODE,
+                message.to_string(),
+            )
+        };
+        let error = diagnostic(
+            at(1, 0),
+            DiagnosticSeverity::ERROR,
+            SYNTAX_CODE,
+            "e".to_string(),
+        );
+        let mut diagnostics = vec![
+            warning(at(2, 0), "later line"),
+            warning(at(1, 0), "same place"),
+            error.clone(),
+            warning(at(1, 0), "same place"),
+            warning(at(0, 4), "first"),
+        ];
+        sort_and_dedup(&mut diagnostics);
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, ["first", "e", "same place", "later line"]);
+    }
+
+    #[test]
+    fn lint_output_is_stable() {
+        let text = "bogus = 1\nfont-size = big\nbogus = 1\ncursor-style: bar\n";
+        assert_eq!(lint(text), lint(text));
+        let lines: Vec<u32> = lint(text).iter().map(|d| d.range.start.line).collect();
+        assert_eq!(lines, [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn window_size_is_a_whole_number_of_cells() {
+        assert_eq!(codes("window-width = 120\n"), Vec::<String>::new());
+        let diagnostics = lint("window-width = 5\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("a whole number of cells"));
+        assert!(diagnostics[0].message.contains("at least 10"));
+        assert_eq!(codes("window-height = 30.5\n"), [INVALID_VALUE_CODE]);
+        assert_eq!(codes("window-width = 800px\n"), [INVALID_VALUE_CODE]);
+    }
+
+    #[test]
+    fn startup_enums_reject_old_choices() {
+        assert_eq!(codes("gtk-tabs-location = left\n"), [INVALID_VALUE_CODE]);
+        assert_eq!(codes("linux-cgroup = v2\n"), [INVALID_VALUE_CODE]);
+        assert_eq!(
+            codes("macos-window-buttons = hidden\n"),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn comma_separated_palette_entries_are_split() {
+        assert_eq!(
+            fixed("palette = 0=#000000,1=#ff0000", MULTIPLE_ENTRIES_CODE),
+            "palette = 0=#000000\npalette = 1=#ff0000"
+        );
+    }
+
+    #[test]
+    fn comma_separated_keybinds_are_split() {
+        assert_eq!(
+            fixed(
+                "  keybind = ctrl+t=new_tab,ctrl+w=close_surface",
+                MULTIPLE_ENTRIES_CODE
+            ),
+            "  keybind = ctrl+t=new_tab\n  keybind = ctrl+w=close_surface"
+        );
+    }
+
+    #[test]
+    fn commas_inside_a_single_entry_are_not_split() {
+        let text = "keybind = ctrl+shift+h=resize_split:left,10\n";
+        assert!(!codes(text).contains(&MULTIPLE_ENTRIES_CODE.to_string()));
+        assert!(!codes("font-family = A, B\n").contains(&MULTIPLE_ENTRIES_CODE.to_string()));
+    }
+
+    #[test]
+    fn union_types_accept_any_member() {
+        for value in ["true", "false", "20", "0"] {
+            let text = format!("background-opacity = 0.8\nbackground-blur = {}\n", value);
+            assert_eq!(codes(&text), Vec::<String>::new(), "{}", value);
+        }
+        for value in ["300", "2.5", "maybe"] {
+            let text = format!("background-opacity = 0.8\nbackground-blur = {}\n", value);
+            assert_eq!(codes(&text), [INVALID_VALUE_CODE], "{}", value);
+        }
+    }
+
+    #[test]
+    fn window_theme_takes_auto_or_an_explicit_mode() {
+        for value in ["auto", "system", "light", "dark", "ghostty"] {
+            assert_eq!(
+                codes(&format!("window-theme = {}\n", value)),
+                Vec::<String>::new()
+            );
+        }
+        assert_eq!(
+            codes("window-theme = Catppuccin Mocha\n"),
+            [INVALID_VALUE_CODE]
+        );
+    }
+
+    #[test]
+    fn diagnostics_link_to_their_code_documentation() {
+        let diagnostics = lint("bogus = 1\nfont-size = big\nfont-size: 12\n");
+        assert_eq!(diagnostics.len(), 3);
+        for d in diagnostics {
+            assert_eq!(d.source.as_deref(), Some(SOURCE));
+            assert_eq!(d.code_description.unwrap().href.as_str(), CODES_URL);
+        }
+    }
+
+    #[test]
+    fn every_code_is_documented_in_the_readme() {
+        let readme = include_str!("../../README.md");
+        for (code, _) in CODES {
+            assert!(
+                readme.contains(&format!("| `{}` |", code)),
+                "`{}` is missing from the README's diagnostic codes",
+                code
+            );
+        }
+    }
+
+    #[test]
+    fn binary_file_gets_a_single_note() {
+        let diagnostics = lint("\u{1}\u{2}\u{3}\u{4}\u{5}\u{6}\u{7}\nbogus = 1\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].severity,
+            Some(DiagnosticSeverity::INFORMATION)
+        );
+        assert_eq!(codes("\0"), [NOT_A_CONFIG_CODE]);
+    }
+
+    #[test]
+    fn keyvalue_lists_are_checked_outside_strict_mode() {
+        let diagnostics = lint("env = PAGER\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert!(lint("env = PAGER=less\n").is_empty());
+    }
+
+    #[test]
+    fn keyvalue_lists_need_name_value_pairs() {
+        assert!(lint_with(&strict(), "env = EDITOR=vim, PAGER=less, LC_ALL=\n").is_empty());
+        let diagnostics = lint_with(&strict(), "env = EDITOR=vim,PAGER,=x\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::ERROR));
+        assert!(diagnostics[0]
+            .message
+            .contains("Malformed pairs `PAGER`, `=x`"));
+    }
+
+    #[test]
+    fn overlong_values_are_a_warning_even_in_strict_mode() {
+        assert!(lint(&format!("title = {}\n", "x".repeat(256))).is_empty());
+        let text = format!("title = {}\n", "x".repeat(257));
+        assert_eq!(codes(&text), [VALUE_TOO_LONG_CODE]);
+        let diagnostics = lint_with(&strict(), &text);
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert!(diagnostics[0].message.contains("257 characters"));
+    }
+
+    #[test]
+    fn length_counts_characters_not_bytes() {
+        assert!(lint(&format!("title = {}\n", "é".repeat(256))).is_empty());
+    }
+
+    #[test]
+    fn options_without_their_prerequisite_have_no_effect() {
+        let diagnostics = lint("background-image-opacity = 0.5\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].severity,
+            Some(DiagnosticSeverity::INFORMATION)
+        );
+        assert_eq!(
+            diagnostics[0].message,
+            "`background-image-opacity` has no effect unless `background-image` is set"
+        );
+        assert!(lint("background-image = bg.png\nbackground-image-opacity = 0.5\n").is_empty());
+    }
+
+    #[test]
+    fn prerequisite_values_are_checked() {
+        let text = "window-theme = dark\nwindow-titlebar-background = #000000\n";
+        assert_eq!(codes(text), [NO_EFFECT_CODE]);
+        let text = "window-theme = ghostty\nwindow-titlebar-background = #000000\n";
+        assert!(lint(text).is_empty());
+
+        assert_eq!(
+            codes("background-opacity = 1\nbackground-blur = 20\n"),
+            [NO_EFFECT_CODE]
+        );
+        assert!(lint("background-opacity = 0.9\nbackground-blur = 20\n").is_empty());
+    }
+
+    #[test]
+    fn a_later_reset_removes_the_prerequisite() {
+        let text =
+            "background-image = bg.png\nbackground-image-fit = cover\nbackground-image = \"\"\n";
+        assert_eq!(codes(text), [NO_EFFECT_CODE]);
+    }
+
+    #[test]
+    fn feature_both_enabled_and_disabled_is_reported() {
+        let diagnostics = lint("shell-integration-features = cursor,sudo,no-cursor\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "`cursor` is both enabled and disabled in the same list"
+        );
+        assert!(lint("shell-integration-features = no-cursor, sudo\n").is_empty());
+    }
+
+    #[test]
+    fn no_prefix_is_unknown_unless_negatable() {
+        let opt: ConfigOption = serde_json::from_value(serde_json::json!({
+            "type": "flags",
+            "description": "Features.",
+            "flags": ["a", "b"]
+        }))
+        .unwrap();
+        assert_eq!(check_flags(&opt, "a,b"), None);
+        assert_eq!(
+            check_flags(&opt, "a,no-b").as_deref(),
+            Some("Unknown feature `no-b`. Expected one of: a, b")
+        );
+    }
+
+    #[test]
+    fn line_validators_run_only_when_enabled() {
+        let text = "config-file = /nonexistent/ghostty-lsp/extra.conf\n";
+        let check_paths = Config {
+            check_paths: true,
+            ..Config::default()
+        };
+        assert!(lint_with(&Config::default(), text).is_empty());
+        assert_eq!(lint_with(&check_paths, text).len(), 1);
+
+        let safe_mode = Config {
+            safe_mode: true,
+            ..check_paths
+        };
+        assert!(!MissingPath.enabled(&safe_mode));
+        assert!(lint_with(&safe_mode, text).is_empty());
+    }
+
+    #[test]
+    fn document_validators_see_the_whole_file() {
+        assert_eq!(codes("background-blur = 20\n").len(), 1);
+        assert!(lint("background-blur = 20\nbackground-opacity = 0.9\n").is_empty());
+    }
+
+    #[test]
+    fn decimals_are_accepted_unless_the_option_is_whole() {
+        assert!(lint("mouse-scroll-multiplier = 0.5\n").is_empty());
+        assert!(lint("quick-terminal-animation-duration = 0.2\n").is_empty());
+        assert_eq!(codes("scrollback-limit = 1000.5\n"), [INVALID_VALUE_CODE]);
+        assert_eq!(codes("mouse-scroll-multiplier = 0\n"), [INVALID_VALUE_CODE]);
+    }
+
+    #[test]
+    fn non_finite_numbers_are_rejected() {
+        for value in ["inf", "NaN", "-infinity"] {
+            let text = format!("font-size = {}\n", value);
+            assert_eq!(codes(&text), [INVALID_VALUE_CODE], "{}", value);
+        }
+    }
+
+    #[test]
+    fn functional_colors_convert_to_hex() {
+        assert_eq!(
+            functional_color_hex("rgb(255, 128, 0)").as_deref(),
+            Some("#ff8000")
+        );
+        assert_eq!(
+            functional_color_hex("RGBA(100% 0% 0% / 0.5)").as_deref(),
+            Some("#ff0000")
+        );
+        assert_eq!(
+            functional_color_hex("hsl(30deg 100% 50%)").as_deref(),
+            Some("#ff8000")
+        );
+        assert_eq!(
+            functional_color_hex("hsl(240, 100%, 25%)").as_deref(),
+            Some("#000080")
+        );
+        assert_eq!(functional_color_hex("rgb(1, 2)"), None);
+        assert_eq!(functional_color_hex("rgb(red, 0, 0)"), None);
+    }
+
+    #[test]
+    fn functional_color_is_fixed_to_hex() {
+        assert_eq!(
+            fixed("background = rgb(40, 44, 52)\n", INVALID_VALUE_CODE),
+            "background = #282c34\n"
+        );
+        let diagnostics = lint("background = rgb(a, b, c)\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("such as `#282c34`"));
+    }
+
+    #[test]
+    fn trailing_backslash_is_not_a_continuation() {
+        assert_eq!(
+            fixed("command = fish --login \\\n", LINE_CONTINUATION_CODE),
+            "command = fish --login \n"
+        );
+        // An escaped backslash and a path are left alone
+        assert!(!codes("command = fish \\\\\n").contains(&LINE_CONTINUATION_CODE.to_string()));
+        assert!(!codes("background-image = C:\\Pictures\\\n")
+            .contains(&LINE_CONTINUATION_CODE.to_string()));
+    }
+
+    #[test]
+    fn boolean_permission_is_fixed_to_a_choice() {
+        assert_eq!(
+            fixed("clipboard-read = true\n", INVALID_VALUE_CODE),
+            "clipboard-read = allow\n"
+        );
+        assert_eq!(
+            fixed("clipboard-write = Off\n", INVALID_VALUE_CODE),
+            "clipboard-write = deny\n"
+        );
+        assert!(lint("clipboard-read = ask\n").is_empty());
+    }
+
+    #[test]
+    fn permission_given_to_a_paste_boolean_is_explained() {
+        let diagnostics = lint("clipboard-paste-protection = ask\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0]
+            .message
+            .starts_with("`clipboard-paste-protection` is a boolean; `ask` is a choice"));
+    }
+
+    #[test]
+    fn summary_counts_by_severity_and_document() {
+        let with_severity = |severity| Diagnostic {
+            severity: Some(severity),
+            ..Diagnostic::default()
+        };
+        let mut summary = Summary::default();
+        summary.add(&[
+            with_severity(DiagnosticSeverity::ERROR),
+            with_severity(DiagnosticSeverity::WARNING),
+        ]);
+        summary.add(&[]);
+        summary.add(&[
+            with_severity(DiagnosticSeverity::INFORMATION),
+            with_severity(DiagnosticSeverity::HINT),
+        ]);
+        assert_eq!(
+            summary,
+            Summary {
+                errors: 1,
+                warnings: 1,
+                information: 1,
+                documents: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn quoted_values_are_checked_without_their_quotes() {
+        assert!(lint("cursor-style = \"block\"\n").is_empty());
+        assert!(lint("background-blur = \"20\"\nbackground-opacity = \"0.9\"\n").is_empty());
+
+        let diagnostics = lint("cursor-style = \"round\"\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].range,
+            span(0, "cursor-style = \"round\"", 16, 5)
+        );
+    }
+
+    #[test]
+    fn window_and_option_key_enums_reject_other_values() {
+        for text in [
+            "window-save-state = sometimes\n",
+            "window-colorspace = p3\n",
+            "macos-option-as-alt = both\n",
+        ] {
+            assert_eq!(codes(text), [INVALID_VALUE_CODE], "{}", text);
+        }
+        assert!(lint(
+            "window-save-state = always\nwindow-colorspace = display-p3\nmacos-option-as-alt = left\n"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn window_decoration_rejects_unknown_modes() {
+        assert_eq!(codes("window-decoration = bogus\n"), [INVALID_VALUE_CODE]);
+        assert!(lint("window-decoration = server\n").is_empty());
+    }
+}
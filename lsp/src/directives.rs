@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use tower_lsp::lsp_types::{Diagnostic, NumberOrString};
+
+const DIRECTIVE_PREFIX: &str = "ghostty-lsp:";
+
+/// Codes a directive suppresses. Empty means every diagnostic.
+type Codes = Vec<String>;
+
+/// Drops diagnostics silenced by comment directives:
+///
+/// - `# ghostty-lsp: disable` silences the line after it
+/// - `# ghostty-lsp: disable-file` silences the whole file
+///
+/// Either can be followed by diagnostic codes (`disable unknown-key`) to
+/// silence only those. Codes match regardless of case and separators, so
+/// `unknownKey` names `unknown-key` too.
+pub fn suppress(text: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let mut file_codes: Vec<Codes> = vec![];
+    let mut line_codes: HashMap<u32, Vec<Codes>> = HashMap::new();
+
+    for (line_num, line) in text.lines().enumerate() {
+        match parse_directive(line) {
+            Some(Directive::File(codes)) => file_codes.push(codes),
+            Some(Directive::NextLine(codes)) => line_codes
+                .entry(line_num as u32 + 1)
+                .or_default()
+                .push(codes),
+            None => {}
+        }
+    }
+    if file_codes.is_empty() && line_codes.is_empty() {
+        return;
+    }
+
+    diagnostics.retain(|d| {
+        let line = line_codes.get(&d.range.start.line).into_iter().flatten();
+        !file_codes
+            .iter()
+            .chain(line)
+            .any(|codes| silences(codes, d))
+    });
+}
+
+enum Directive {
+    NextLine(Codes),
+    File(Codes),
+}
+
+fn parse_directive(line: &str) -> Option<Directive> {
+    let comment = line.trim_start().strip_prefix('#')?;
+    let rest = comment.trim_start().strip_prefix(DIRECTIVE_PREFIX)?;
+    let mut words = rest
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|w| !w.is_empty());
+    let kind = words.next()?;
+    let codes = words.map(normalize_code).collect();
+    match kind {
+        "disable" => Some(Directive::NextLine(codes)),
+        "disable-file" => Some(Directive::File(codes)),
+        _ => None,
+    }
+}
+
+fn silences(codes: &Codes, diagnostic: &Diagnostic) -> bool {
+    if codes.is_empty() {
+        return true;
+    }
+    match &diagnostic.code {
+        Some(NumberOrString::String(code)) => codes.contains(&normalize_code(code)),
+        _ => false,
+    }
+}
+
+fn normalize_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| !matches!(c, '-' | '_'))
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
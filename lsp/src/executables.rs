@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::paths;
+
+/// Memoises PATH lookups so repeated validation passes don't hit the filesystem
+/// on every keystroke.
+#[derive(Default)]
+pub struct ExecutableCache {
+    found: Mutex<HashMap<String, bool>>,
+}
+
+impl ExecutableCache {
+    pub fn exists(&self, program: &str) -> bool {
+        if let Some(found) = self.found.lock().unwrap().get(program) {
+            return *found;
+        }
+        let found = resolve(program);
+        self.found
+            .lock()
+            .unwrap()
+            .insert(program.to_string(), found);
+        found
+    }
+}
+
+/// Returns the program named by a `command` value along with its byte offset
+/// in the value, skipping Ghostty's `direct:`/`shell:` prefixes and quotes.
+pub fn program_token(value: &str) -> Option<(usize, &str)> {
+    let mut offset = 0;
+    let mut rest = value;
+    for prefix in ["direct:", "shell:"] {
+        if let Some(stripped) = rest.strip_prefix(prefix) {
+            offset += prefix.len();
+            rest = stripped;
+            break;
+        }
+    }
+
+    let trimmed = rest.trim_start();
+    offset += rest.len() - trimmed.len();
+
+    let token = match trimmed.chars().next()? {
+        quote @ ('"' | '\'') => {
+            let inner = &trimmed[1..];
+            offset += 1;
+            inner.split(quote).next()?
+        }
+        _ => trimmed.split_whitespace().next()?,
+    };
+
+    (!token.is_empty()).then_some((offset, token))
+}
+
+fn resolve(program: &str) -> bool {
+    if program.contains('/') || program.contains(std::path::MAIN_SEPARATOR) {
+        return is_executable(&PathBuf::from(paths::expand(program)));
+    }
+
+    let Some(path) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&path).any(|dir| {
+        let candidate = dir.join(program);
+        is_executable(&candidate)
+            || (cfg!(windows) && is_executable(&candidate.with_extension("exe")))
+    })
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
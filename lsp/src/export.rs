@@ -0,0 +1,161 @@
+use crate::formatting::format_text;
+use crate::resolve::{ResolvedConfig, ResolvedValue};
+use crate::schema::GhosttySchema;
+
+/// Renders the effective configuration as a canonical config file: no
+/// comments, keys sorted, includes already inlined, `=` aligned and values
+/// normalised, so two exports can be diffed directly.
+pub fn normalized(schema: &GhosttySchema, config: &ResolvedConfig) -> String {
+    let mut lines = vec![];
+    for (key, value) in &config.values {
+        let values = match value {
+            ResolvedValue::Single(value) => std::slice::from_ref(value),
+            ResolvedValue::List(values) => values.as_slice(),
+        };
+        for value in values {
+            let value = quote_if_needed(normalize_value(schema, key, value));
+            lines.push(format!("{} = {}", key, value));
+        }
+    }
+
+    let mut text = format_text(&lines.join("\n"));
+    if !text.is_empty() {
+        text.push('\n');
+    }
+    text
+}
+
+fn normalize_value(schema: &GhosttySchema, key: &str, value: &str) -> String {
+    let option_type = schema
+        .options
+        .get(key)
+        .map(|opt| opt.option_type.as_str())
+        .unwrap_or_default();
+
+    match option_type {
+        "boolean" => match value {
+            "1" | "t" | "T" => "true".to_string(),
+            "0" | "f" | "F" => "false".to_string(),
+            _ => value.to_string(),
+        },
+        "color" => lowercase_hex(value),
+        // `palette = 5=#AABBCC`
+        _ if key == "palette" => match value.split_once('=') {
+            Some((index, colour)) => format!("{}={}", index.trim(), lowercase_hex(colour.trim())),
+            None => value.to_string(),
+        },
+        _ => value.to_string(),
+    }
+}
+
+/// Resolved values have had their quotes removed. Quotes are only put back
+/// where Ghostty would otherwise read the value differently: surrounding
+/// whitespace it would trim, or quotes of its own it would strip.
+fn quote_if_needed(value: String) -> String {
+    let own_quotes = value.len() >= 2 && value.starts_with('"') && value.ends_with('"');
+    if value.trim() != value || own_quotes {
+        format!("\"{}\"", value)
+    } else {
+        value
+    }
+}
+
+fn lowercase_hex(value: &str) -> String {
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    if matches!(hex.len(), 3 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        value.to_ascii_lowercase()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Formats `structured` can serialise the effective configuration as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StructuredFormat {
+    Json,
+    Toml,
+}
+
+impl StructuredFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "toml" => Some(Self::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// Serialises the effective configuration as a key to value map for other
+/// tools. Repeatable keys become arrays holding every value in order.
+pub fn structured(config: &ResolvedConfig, format: StructuredFormat) -> Result<String, String> {
+    match format {
+        StructuredFormat::Json => {
+            serde_json::to_string_pretty(&config.values).map_err(|e| e.to_string())
+        }
+        StructuredFormat::Toml => toml::to_string(&config.values).map_err(|e| e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolve::resolve_config;
+
+    fn export(text: &str) -> String {
+        let schema = GhosttySchema::load();
+        normalized(&schema, &resolve_config(&schema, text, None, false))
+    }
+
+    #[test]
+    fn quoted_values_are_exported_as_ghostty_reads_them() {
+        assert_eq!(
+            export("command = \"/usr/bin/env fish\"\n"),
+            "command = /usr/bin/env fish\n"
+        );
+    }
+
+    #[test]
+    fn quotes_are_kept_where_they_matter() {
+        assert_eq!(export("title = \" padded \"\n"), "title = \" padded \"\n");
+    }
+
+    fn structured_export(text: &str, format: StructuredFormat) -> String {
+        let schema = GhosttySchema::load();
+        structured(&resolve_config(&schema, text, None, false), format).unwrap()
+    }
+
+    #[test]
+    fn json_export_keeps_the_last_value_and_every_repeat() {
+        let text = "font-size = 12\nfont-size = 14\nfont-family = A\nfont-family = B\n";
+        let json: serde_json::Value =
+            serde_json::from_str(&structured_export(text, StructuredFormat::Json)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "font-family": ["A", "B"], "font-size": "14" })
+        );
+    }
+
+    #[test]
+    fn toml_export_is_valid_toml() {
+        let text = "font-size = 14\nfont-family = A\nfont-family = B\n";
+        let toml: toml::Value = structured_export(text, StructuredFormat::Toml)
+            .parse()
+            .unwrap();
+        assert_eq!(toml["font-size"].as_str(), Some("14"));
+        assert_eq!(toml["font-family"].as_array().map(Vec::len), Some(2));
+    }
+
+    #[test]
+    fn format_names_are_case_insensitive() {
+        assert_eq!(
+            StructuredFormat::parse("JSON"),
+            Some(StructuredFormat::Json)
+        );
+        assert_eq!(
+            StructuredFormat::parse("toml"),
+            Some(StructuredFormat::Toml)
+        );
+        assert_eq!(StructuredFormat::parse("yaml"), None);
+    }
+}
@@ -0,0 +1,165 @@
+use tower_lsp::lsp_types::{Position, Range, TextEdit};
+
+use crate::parse::parse_assignment;
+
+/// Formats the whole document: every block of consecutive assignments gets its
+/// `=` aligned and trailing whitespace is removed.
+pub fn format_document(text: &str) -> Vec<TextEdit> {
+    let lines: Vec<&str> = text.lines().collect();
+    if lines.is_empty() {
+        return vec![];
+    }
+    format_block_range(&lines, 0, lines.len() - 1, line_ending(text))
+}
+
+/// Returns `text` formatted the same way as [`format_document`].
+pub fn format_text(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    format_lines(&lines).join("\n")
+}
+
+/// Formats the lines touched by `range`. The range is snapped to whole lines and
+/// widened to the enclosing assignment blocks, so a selection that cuts a block
+/// mid-way still leaves the whole block consistently aligned.
+pub fn format_range(text: &str, range: Range) -> Vec<TextEdit> {
+    let lines: Vec<&str> = text.lines().collect();
+    if lines.is_empty() {
+        return vec![];
+    }
+
+    let last = lines.len() - 1;
+    let mut start = (range.start.line as usize).min(last);
+    let mut end = (range.end.line as usize).min(last);
+    // A selection ending at column 0 doesn't include that line
+    if range.end.character == 0 && end > start && range.end.line as usize <= last {
+        end -= 1;
+    }
+
+    while start > 0 && is_assignment(lines[start]) && is_assignment(lines[start - 1]) {
+        start -= 1;
+    }
+    while end < last && is_assignment(lines[end]) && is_assignment(lines[end + 1]) {
+        end += 1;
+    }
+
+    format_block_range(&lines, start, end, line_ending(text))
+}
+
+/// `\r\n` when the document uses it, so replaced lines keep its endings.
+fn line_ending(text: &str) -> &'static str {
+    if text.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    }
+}
+
+fn format_block_range(
+    lines: &[&str],
+    start: usize,
+    end: usize,
+    line_ending: &str,
+) -> Vec<TextEdit> {
+    let original = &lines[start..=end];
+    let formatted = format_lines(original);
+    if formatted
+        .iter()
+        .map(String::as_str)
+        .eq(original.iter().copied())
+    {
+        return vec![];
+    }
+
+    let end_col = lines[end].encode_utf16().count() as u32;
+    vec![TextEdit::new(
+        Range::new(
+            Position::new(start as u32, 0),
+            Position::new(end as u32, end_col),
+        ),
+        formatted.join(line_ending),
+    )]
+}
+
+fn is_assignment(line: &str) -> bool {
+    parse_assignment(line).is_some()
+}
+
+fn format_lines(lines: &[&str]) -> Vec<String> {
+    let mut output = Vec::with_capacity(lines.len());
+    let mut block: Vec<(&str, &str)> = vec![];
+
+    for line in lines {
+        match parse_assignment(line) {
+            Some(assignment) => block.push((assignment.key, assignment.value)),
+            None => {
+                flush_block(&mut block, &mut output);
+                output.push(line.trim_end().to_string());
+            }
+        }
+    }
+    flush_block(&mut block, &mut output);
+
+    output
+}
+
+fn flush_block(block: &mut Vec<(&str, &str)>, output: &mut Vec<String>) {
+    let width = block.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+    for (key, value) in block.drain(..) {
+        let line = format!("{:<width$} = {}", key, value, width = width);
+        output.push(line.trim_end().to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(text: &str, edits: &[TextEdit]) -> String {
+        let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+        for edit in edits.iter().rev() {
+            let (start, end) = (edit.range.start.line as usize, edit.range.end.line as usize);
+            lines.splice(start..=end, [edit.new_text.clone()]);
+        }
+        lines.join("\n")
+    }
+
+    #[test]
+    fn aligns_each_block_separately() {
+        let text = "a = 1\nlong-key = 2\n\nb=3\n";
+        let edits = format_document(text);
+        assert_eq!(apply(text, &edits), "a        = 1\nlong-key = 2\n\nb = 3");
+    }
+
+    #[test]
+    fn formatted_document_has_no_edits() {
+        assert!(format_document("a  = 1\nbb = 2\n").is_empty());
+    }
+
+    #[test]
+    fn range_is_widened_to_the_whole_block() {
+        let text = "# comment\na = 1\nlong-key = 2\n\nb=3\n";
+        let range = Range::new(Position::new(1, 0), Position::new(1, 3));
+        let edits = format_range(text, range);
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].range.start.line, 1);
+        assert_eq!(edits[0].range.end.line, 2);
+        assert_eq!(edits[0].new_text, "a        = 1\nlong-key = 2");
+    }
+
+    #[test]
+    fn range_outside_a_block_leaves_others_alone() {
+        let text = "a=1\n\nb=2\n";
+        let range = Range::new(Position::new(2, 0), Position::new(2, 3));
+        let edits = format_range(text, range);
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].range.start.line, 2);
+        assert_eq!(edits[0].new_text, "b = 2");
+    }
+
+    #[test]
+    fn keeps_crlf_line_endings() {
+        let text = "a = 1\r\nlong-key = 2\r\n";
+        let edits = format_document(text);
+        assert_eq!(edits[0].new_text, "a        = 1\r\nlong-key = 2");
+    }
+}
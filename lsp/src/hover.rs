@@ -0,0 +1,144 @@
+use tower_lsp::lsp_types::{Hover, HoverContents, MarkupContent, MarkupKind, Position, Range};
+
+use crate::completion::format_key_documentation;
+use crate::parse::{utf16_col, ParsedDocument};
+use crate::references;
+use crate::schema::GhosttySchema;
+
+/// The option's documentation when the cursor is on a known key, or the
+/// action's when it's on a known `keybind` action.
+pub fn hover(
+    schema: &GhosttySchema,
+    document: &ParsedDocument,
+    position: Position,
+) -> Option<Hover> {
+    if let Some((range, action)) = references::action_span_at(schema, document, position) {
+        return action_hover(schema, &action, range);
+    }
+
+    let line = document.line(position.line as usize)?;
+    let assignment = line.assignment()?;
+    let line = line.text.as_str();
+    let opt = schema.options.get(assignment.key)?;
+
+    let start = utf16_col(line, assignment.key_start);
+    let end = utf16_col(line, assignment.key_start + assignment.key.len());
+    if !(start..=end).contains(&position.character) {
+        return None;
+    }
+
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: format!(
+                "**{}**\n\n{}",
+                assignment.key,
+                format_key_documentation(assignment.key, opt)
+            ),
+        }),
+        range: Some(Range::new(
+            Position::new(position.line, start),
+            Position::new(position.line, end),
+        )),
+    })
+}
+
+/// The action's description and, for actions that take one, its argument.
+fn action_hover(schema: &GhosttySchema, action: &str, range: Range) -> Option<Hover> {
+    let keybind = schema.types.as_ref()?.keybind.as_ref()?;
+    let description = keybind
+        .action_descriptions
+        .as_ref()
+        .and_then(|d| d.get(action));
+    let argument = keybind
+        .action_arguments
+        .as_ref()
+        .and_then(|a| a.get(action));
+    let known = keybind.actions.iter().flatten().any(|a| a == action);
+    if !known && description.is_none() {
+        return None;
+    }
+
+    let mut value = format!("**{}**", action);
+    if let Some(description) = description {
+        value.push_str(&format!("\n\n{}", description));
+    }
+    match argument {
+        Some(argument) => value.push_str(&format!(
+            "\n\nTakes {} argument: {}. Example: `{}`",
+            if argument.required {
+                "a required"
+            } else {
+                "an optional"
+            },
+            argument.expects,
+            argument.example
+        )),
+        None => value.push_str("\n\nTakes no argument"),
+    }
+
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        }),
+        range: Some(range),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The hover's markdown, if there is one at `line`, `character`.
+    fn hover_text(text: &str, line: u32, character: u32) -> Option<String> {
+        let document = ParsedDocument::new(text);
+        let hover = hover(
+            &GhosttySchema::load(),
+            &document,
+            Position::new(line, character),
+        )?;
+        match hover.contents {
+            HoverContents::Markup(markup) => Some(markup.value),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn window_theme_points_at_theme() {
+        let text = hover_text("window-theme = dark", 0, 3).unwrap();
+        assert!(text.starts_with("**window-theme**"));
+        assert!(text.contains("**Valid values:** auto, system, light, dark, ghostty"));
+        assert!(text.contains("**See also:** `theme`"));
+    }
+
+    #[test]
+    fn only_the_key_has_a_hover() {
+        assert!(hover_text("window-theme = dark", 0, 17).is_none());
+        assert!(hover_text("not-an-option = dark", 0, 3).is_none());
+    }
+
+    #[test]
+    fn keybind_action_describes_itself_and_its_argument() {
+        let text = hover_text("keybind = ctrl+1=goto_tab:1", 0, 20).unwrap();
+        assert_eq!(
+            text,
+            "**goto_tab**\n\nGo to the tab with the given number\n\n\
+             Takes a required argument: the tab number, starting at 1. Example: `goto_tab:1`"
+        );
+    }
+
+    #[test]
+    fn keybind_action_without_argument_says_so() {
+        let text = hover_text("keybind = ctrl+n=new_window", 0, 20).unwrap();
+        assert!(text.ends_with("Open a new window\n\nTakes no argument"));
+    }
+
+    #[test]
+    fn keybind_trigger_shows_the_key_hover() {
+        assert!(hover_text("keybind = ctrl+n=new_window", 0, 12).is_none());
+        assert!(hover_text("keybind = ctrl+n=new_window", 0, 3)
+            .unwrap()
+            .starts_with("**keybind**"));
+    }
+}
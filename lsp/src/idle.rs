@@ -0,0 +1,60 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// Tracks open documents so the server can exit after sitting idle with none
+/// open. The editor starts a fresh server when a config is opened again.
+pub struct IdleMonitor {
+    open_documents: AtomicUsize,
+    last_activity: Mutex<Instant>,
+    shutdown: Notify,
+}
+
+impl Default for IdleMonitor {
+    fn default() -> Self {
+        Self {
+            open_documents: AtomicUsize::new(0),
+            last_activity: Mutex::new(Instant::now()),
+            shutdown: Notify::new(),
+        }
+    }
+}
+
+impl IdleMonitor {
+    pub fn opened(&self) {
+        self.open_documents.fetch_add(1, Ordering::Relaxed);
+        self.touch();
+    }
+
+    pub fn closed(&self) {
+        // Saturate: a close for a document opened before tracking started
+        let _ = self
+            .open_documents
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1));
+        self.touch();
+    }
+
+    pub fn touch(&self) {
+        *self.last_activity.lock().unwrap() = Instant::now();
+    }
+
+    /// Resolves once no document has been open for `timeout`, then signals
+    /// [`shutdown_requested`](Self::shutdown_requested).
+    pub async fn watch(&self, timeout: Duration) {
+        loop {
+            let idle_for = self.last_activity.lock().unwrap().elapsed();
+            if self.open_documents.load(Ordering::Relaxed) == 0 && idle_for >= timeout {
+                self.shutdown.notify_one();
+                return;
+            }
+            // Check again when the current idle stretch would run out
+            let wait = timeout.saturating_sub(idle_for).max(Duration::from_secs(1));
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    pub async fn shutdown_requested(&self) {
+        self.shutdown.notified().await;
+    }
+}
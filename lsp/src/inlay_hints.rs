@@ -0,0 +1,86 @@
+use tower_lsp::lsp_types::{InlayHint, InlayHintLabel, Position, Range};
+
+use crate::parse::{utf16_col, ParsedDocument};
+use crate::references::keybind_action;
+use crate::schema::GhosttySchema;
+
+/// Appends the schema's description after each keybind's action, for the lines
+/// in `range`. Unrecognised actions get no hint.
+pub fn keybind_action_hints(
+    schema: &GhosttySchema,
+    document: &ParsedDocument,
+    range: Range,
+) -> Vec<InlayHint> {
+    let Some(descriptions) = schema
+        .types
+        .as_ref()
+        .and_then(|t| t.keybind.as_ref())
+        .and_then(|k| k.action_descriptions.as_ref())
+    else {
+        return vec![];
+    };
+
+    let mut hints = vec![];
+    for (line_num, line) in document.lines().iter().enumerate() {
+        let line_num = line_num as u32;
+        if line_num < range.start.line || line_num > range.end.line {
+            continue;
+        }
+        let Some((_, name)) = keybind_action(schema, line) else {
+            continue;
+        };
+        let Some(description) = descriptions.get(name) else {
+            continue;
+        };
+        let Some(assignment) = line.assignment() else {
+            continue;
+        };
+
+        let end = assignment.value_start + assignment.value.len();
+        hints.push(InlayHint {
+            position: Position::new(line_num, utf16_col(&line.text, end)),
+            label: InlayHintLabel::String(format!("→ {}", description)),
+            kind: None,
+            text_edits: None,
+            tooltip: None,
+            padding_left: Some(true),
+            padding_right: None,
+            data: None,
+        });
+    }
+
+    hints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hints(text: &str) -> Vec<InlayHint> {
+        let range = Range::new(Position::new(0, 0), Position::new(u32::MAX, 0));
+        keybind_action_hints(&GhosttySchema::load(), &ParsedDocument::new(text), range)
+    }
+
+    fn label(hint: &InlayHint) -> &str {
+        match &hint.label {
+            InlayHintLabel::String(label) => label,
+            InlayHintLabel::LabelParts(_) => panic!("expected a plain label"),
+        }
+    }
+
+    #[test]
+    fn known_action_is_described_after_the_value() {
+        let hints = hints("font-size = 14\nkeybind = ctrl+n=new_window  \n");
+        assert_eq!(hints.len(), 1);
+        assert_eq!(hints[0].position, Position::new(1, 27));
+        assert_eq!(label(&hints[0]), "→ Open a new window");
+
+        let hints = self::hints("keybind = global:ctrl+1=goto_tab:1\n");
+        assert_eq!(label(&hints[0]), "→ Go to the tab with the given number");
+    }
+
+    #[test]
+    fn unknown_action_gets_no_hint() {
+        assert!(hints("keybind = ctrl+n=new_windoww\nkeybind = ctrl+n\n").is_empty());
+    }
+}
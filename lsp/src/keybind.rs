@@ -0,0 +1,429 @@
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::parse::{split_keybind, strip_keybind_prefixes};
+use crate::schema::KeybindType;
+
+/// Modifiers in Ghostty's canonical order, each with the aliases it accepts.
+const MODIFIERS: &[(&str, &[&str])] = &[
+    ("ctrl", &["ctrl", "control"]),
+    ("alt", &["alt", "opt", "option"]),
+    ("shift", &["shift"]),
+    ("super", &["super", "cmd", "command"]),
+];
+
+/// A problem in a keybind value. Offsets are byte offsets into the value.
+pub struct KeybindProblem {
+    pub start: usize,
+    pub len: usize,
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+    pub fix: Option<KeybindFix>,
+}
+
+pub struct KeybindFix {
+    pub title: &'static str,
+    pub start: usize,
+    pub len: usize,
+    pub new_text: String,
+}
+
+/// Returns the modifier's rank in canonical order and its canonical name.
+fn canonical_modifier(token: &str) -> Option<(usize, &'static str)> {
+    MODIFIERS
+        .iter()
+        .enumerate()
+        .find(|(_, (_, aliases))| aliases.iter().any(|a| a.eq_ignore_ascii_case(token)))
+        .map(|(rank, (name, _))| (rank, *name))
+}
+
+pub fn check_keybind(value: &str, keybind: &KeybindType, check_order: bool) -> Vec<KeybindProblem> {
+    let mut problems = vec![];
+
+    let prefixes = keybind.prefixes.as_deref().unwrap_or_default();
+    let (_, rest) = strip_keybind_prefixes(value, prefixes);
+    let Some((trigger, action)) = split_keybind(rest) else {
+        return problems;
+    };
+
+    // Sequences (`ctrl+a>n`) are checked one step at a time
+    let mut offset = value.len() - rest.len();
+    for step in trigger.split('>') {
+        check_modifiers(step, offset, check_order, &mut problems);
+        problems.extend(check_key(step, offset, keybind));
+        offset += step.len() + 1;
+    }
+
+    let action_start = value.len() - rest.len() + trigger.len() + 1;
+    let (qualifiers, inner) = strip_keybind_prefixes(action, prefixes);
+    if !qualifiers.is_empty() {
+        problems.push(misplaced_prefix(value, rest, trigger, action, inner));
+    }
+    let inner_start = action_start + action.len() - inner.len();
+    problems.extend(check_action_argument(inner, inner_start, keybind));
+
+    problems
+}
+
+/// Prefixes such as `performable:` qualify the trigger, so Ghostty rejects
+/// them in front of the action (`ctrl+c=performable:copy_to_clipboard`). The
+/// fix moves them to the front of the value.
+fn misplaced_prefix(
+    value: &str,
+    rest: &str,
+    trigger: &str,
+    action: &str,
+    inner: &str,
+) -> KeybindProblem {
+    let prefix_len = action.len() - inner.len();
+    let moved = &action[..prefix_len];
+    let leading = &value[..value.len() - rest.len()];
+    KeybindProblem {
+        start: value.len() - action.len(),
+        len: prefix_len,
+        severity: DiagnosticSeverity::WARNING,
+        message: format!(
+            "`{}` goes before the trigger, not the action: `{}{}{}={}`",
+            moved, leading, moved, trigger, inner
+        ),
+        fix: Some(KeybindFix {
+            title: "Move prefix before the trigger",
+            start: 0,
+            len: value.len(),
+            new_text: format!("{}{}{}={}", leading, moved, trigger, inner),
+        }),
+    }
+}
+
+/// Flags a bare action that needs an argument (`goto_tab`) and an argument
+/// passed to an action that takes none (`new_tab:1`). Unknown actions are skipped.
+fn check_action_argument(
+    action: &str,
+    offset: usize,
+    keybind: &KeybindType,
+) -> Option<KeybindProblem> {
+    let (name, argument) = match action.split_once(':') {
+        Some((name, argument)) => (name, Some(argument)),
+        None => (action, None),
+    };
+    if !keybind.actions.iter().flatten().any(|a| a == name) {
+        return None;
+    }
+
+    let spec = keybind.action_arguments.as_ref().and_then(|a| a.get(name));
+    match (spec, argument) {
+        (Some(spec), None) if spec.required => Some(KeybindProblem {
+            start: offset,
+            len: name.len(),
+            severity: DiagnosticSeverity::WARNING,
+            message: format!(
+                "`{}` needs an argument: {}, e.g. `{}`",
+                name, spec.expects, spec.example
+            ),
+            fix: None,
+        }),
+        (Some(spec), Some("")) if spec.required => Some(KeybindProblem {
+            start: offset,
+            len: action.len(),
+            severity: DiagnosticSeverity::WARNING,
+            message: format!(
+                "`{}` needs an argument after `:`: {}, e.g. `{}`",
+                name, spec.expects, spec.example
+            ),
+            fix: None,
+        }),
+        (None, Some(_)) => Some(KeybindProblem {
+            start: offset,
+            len: action.len(),
+            severity: DiagnosticSeverity::WARNING,
+            message: format!("`{}` doesn't take an argument", name),
+            fix: Some(KeybindFix {
+                title: "Remove argument",
+                start: offset + name.len(),
+                len: action.len() - name.len(),
+                new_text: String::new(),
+            }),
+        }),
+        _ => None,
+    }
+}
+
+/// The key a trigger step ends in, after its modifiers. `ctrl++` ends in `+`.
+fn step_key(step: &str) -> &str {
+    if step.ends_with("++") {
+        return "+";
+    }
+    step.rsplit('+').next().unwrap_or(step)
+}
+
+/// Flags a trigger key that's neither a single character nor one of the
+/// schema's named keys, such as `enterr`. Keys may carry a `physical:` prefix.
+fn check_key(step: &str, offset: usize, keybind: &KeybindType) -> Option<KeybindProblem> {
+    let keys = keybind.keys.as_ref()?;
+    let key = step_key(step);
+    let name = key.strip_prefix("physical:").unwrap_or(key);
+    if name.is_empty()
+        || name.chars().count() == 1
+        || keys.iter().any(|k| k.eq_ignore_ascii_case(name))
+    {
+        return None;
+    }
+    // Ghostty only binds keyboard triggers; mouse buttons have their own options
+    let lower = name.to_ascii_lowercase();
+    let message = if lower.starts_with("mouse") || lower.starts_with("button") {
+        format!(
+            "`{}` isn't a key. Keybinds only take keyboard triggers; mouse behaviour is set with the `mouse-*` options",
+            name
+        )
+    } else {
+        format!(
+            "Unknown key `{}`. Expected a single character or a key name such as `enter`, `space` or `f1`",
+            name
+        )
+    };
+    Some(KeybindProblem {
+        start: offset + step.len() - key.len(),
+        len: key.len(),
+        severity: DiagnosticSeverity::WARNING,
+        message,
+        fix: None,
+    })
+}
+
+fn check_modifiers(
+    step: &str,
+    offset: usize,
+    check_order: bool,
+    problems: &mut Vec<KeybindProblem>,
+) {
+    // `ctrl++` binds the `+` key itself
+    let modifiers_part = if let Some(mods) = step.strip_suffix("++") {
+        mods
+    } else if let Some(i) = step.rfind('+') {
+        &step[..i]
+    } else {
+        return;
+    };
+
+    let mut modifiers: Vec<(usize, &str, usize, &'static str)> = vec![];
+    let mut token_start = offset;
+    for token in modifiers_part.split('+') {
+        match canonical_modifier(token) {
+            Some((rank, name)) => modifiers.push((token_start, token, rank, name)),
+            // Unknown modifiers are reported elsewhere; don't try to fix them here
+            None => return,
+        }
+        token_start += token.len() + 1;
+    }
+
+    let mut normalized: Vec<(usize, &str)> = modifiers.iter().map(|m| (m.2, m.3)).collect();
+    normalized.sort();
+    normalized.dedup();
+    let fix = || KeybindFix {
+        title: "Normalize modifiers",
+        start: offset,
+        len: modifiers_part.len(),
+        new_text: normalized
+            .iter()
+            .map(|(_, name)| *name)
+            .collect::<Vec<_>>()
+            .join("+"),
+    };
+
+    let mut has_duplicate = false;
+    for (i, (start, token, rank, name)) in modifiers.iter().enumerate() {
+        if let Some((_, first, _, _)) = modifiers[..i].iter().find(|m| m.2 == *rank) {
+            has_duplicate = true;
+            let message = if first.eq_ignore_ascii_case(token) {
+                format!("Duplicate modifier `{}`", token)
+            } else {
+                format!("`{}` duplicates `{}` (both are {})", token, first, name)
+            };
+            problems.push(KeybindProblem {
+                start: *start,
+                len: token.len(),
+                severity: DiagnosticSeverity::WARNING,
+                message,
+                fix: Some(fix()),
+            });
+        }
+    }
+
+    let in_order = modifiers.windows(2).all(|w| w[0].2 <= w[1].2);
+    if check_order && !has_duplicate && !in_order {
+        problems.push(KeybindProblem {
+            start: offset,
+            len: modifiers_part.len(),
+            severity: DiagnosticSeverity::INFORMATION,
+            message: "Modifiers are not in canonical order (ctrl+alt+shift+super)".to_string(),
+            fix: Some(fix()),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::GhosttySchema;
+
+    fn check(value: &str, check_order: bool) -> Vec<KeybindProblem> {
+        let schema = GhosttySchema::load();
+        let keybind = schema.types.and_then(|t| t.keybind).unwrap();
+        check_keybind(value, &keybind, check_order)
+    }
+
+    /// `value` with the problem's fix applied.
+    fn fixed(value: &str, problem: &KeybindProblem) -> String {
+        let fix = problem.fix.as_ref().expect("problem has a fix");
+        let mut value = value.to_string();
+        value.replace_range(fix.start..fix.start + fix.len, &fix.new_text);
+        value
+    }
+
+    #[test]
+    fn duplicate_modifier_is_reported_and_fixed() {
+        let problems = check("ctrl+ctrl+a=copy_to_clipboard", false);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].message, "Duplicate modifier `ctrl`");
+        assert_eq!(
+            fixed("ctrl+ctrl+a=copy_to_clipboard", &problems[0]),
+            "ctrl+a=copy_to_clipboard"
+        );
+    }
+
+    #[test]
+    fn alias_of_the_same_modifier_is_a_duplicate() {
+        let problems = check("cmd+super+a=copy_to_clipboard", false);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].message.contains("`super` duplicates `cmd`"));
+    }
+
+    #[test]
+    fn order_is_only_checked_when_asked() {
+        assert!(check("shift+ctrl+a=copy_to_clipboard", false).is_empty());
+
+        let problems = check("shift+ctrl+a=copy_to_clipboard", true);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].severity, DiagnosticSeverity::INFORMATION);
+        assert_eq!(
+            fixed("shift+ctrl+a=copy_to_clipboard", &problems[0]),
+            "ctrl+shift+a=copy_to_clipboard"
+        );
+    }
+
+    #[test]
+    fn each_step_of_a_sequence_is_checked() {
+        let problems = check("ctrl+a>alt+alt+n=new_window", false);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].start, "ctrl+a>alt+".len());
+    }
+
+    #[test]
+    fn binding_the_plus_key_is_not_a_modifier() {
+        assert_eq!(
+            split_keybind("ctrl++=increase_font_size:1"),
+            Some(("ctrl++", "increase_font_size:1"))
+        );
+        assert!(check("ctrl++=increase_font_size:1", true).is_empty());
+
+        // The action after the `+` key is still checked
+        let problems = check("ctrl++=goto_tab", true);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0]
+            .message
+            .starts_with("`goto_tab` needs an argument"));
+    }
+
+    #[test]
+    fn action_missing_its_argument_is_reported() {
+        let problems = check("ctrl+a=goto_tab", false);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0]
+            .message
+            .starts_with("`goto_tab` needs an argument"));
+
+        let problems = check("ctrl+a=goto_tab:", false);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].message.contains("after `:`"));
+
+        assert!(check("ctrl+a=goto_tab:1", false).is_empty());
+    }
+
+    #[test]
+    fn unexpected_argument_is_reported_and_removed() {
+        let problems = check("ctrl+t=new_window:1", false);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(
+            fixed("ctrl+t=new_window:1", &problems[0]),
+            "ctrl+t=new_window"
+        );
+    }
+
+    #[test]
+    fn unknown_actions_are_left_alone() {
+        assert!(check("ctrl+a=not_an_action:1", false).is_empty());
+    }
+
+    #[test]
+    fn unknown_trigger_key_is_reported() {
+        let problems = check("ctrl+enterr=new_window", false);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].message.starts_with("Unknown key `enterr`"));
+        assert_eq!((problems[0].start, problems[0].len), (5, 6));
+    }
+
+    #[test]
+    fn known_trigger_keys_are_accepted() {
+        for value in [
+            "ctrl+a=new_window",
+            "ctrl+arrow_up=new_window",
+            "ctrl+Enter=new_window",
+            "ctrl+physical:key_a=new_window",
+            "super+shift+é=new_window",
+            "f1=new_window",
+        ] {
+            assert!(check(value, false).is_empty(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn mouse_trigger_is_explained() {
+        for value in ["ctrl+mouse_left=copy_to_clipboard", "Button4=new_window"] {
+            let problems = check(value, false);
+            assert_eq!(problems.len(), 1, "{}", value);
+            assert!(
+                problems[0]
+                    .message
+                    .contains("isn't a key. Keybinds only take keyboard triggers"),
+                "{}",
+                problems[0].message
+            );
+        }
+    }
+
+    #[test]
+    fn prefix_in_front_of_the_action_is_moved_before_the_trigger() {
+        let value = "ctrl+c=performable:copy_to_clipboard";
+        let problems = check(value, false);
+        assert_eq!(problems.len(), 1);
+        assert_eq!((problems[0].start, problems[0].len), (7, 12));
+        assert_eq!(
+            problems[0].message,
+            "`performable:` goes before the trigger, not the action: `performable:ctrl+c=copy_to_clipboard`"
+        );
+        assert_eq!(
+            fixed(value, &problems[0]),
+            "performable:ctrl+c=copy_to_clipboard"
+        );
+        assert!(check("performable:ctrl+c=copy_to_clipboard", false).is_empty());
+    }
+
+    #[test]
+    fn action_behind_a_misplaced_prefix_is_still_checked() {
+        let problems = check("ctrl+a=performable:goto_tab", false);
+        let messages: Vec<&str> = problems.iter().map(|p| p.message.as_str()).collect();
+        assert_eq!(messages.len(), 2, "{:?}", messages);
+        assert!(messages[0].starts_with("`performable:` goes before the trigger"));
+        assert!(messages[1].starts_with("`goto_tab` needs an argument"));
+        assert_eq!(problems[1].start, 19);
+    }
+}
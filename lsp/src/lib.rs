@@ -0,0 +1,67 @@
+//! Completion and validation for Ghostty config files. The `ghostty-lsp`
+//! binary is a thin language server over these modules; other tools can call
+//! [`get_completions`] and [`lint`] directly.
+
+use tower_lsp::lsp_types::{CompletionItem, Diagnostic, Position, Url};
+
+use config::Config;
+use executables::ExecutableCache;
+use schema::GhosttySchema;
+
+pub mod code_actions;
+pub mod completion;
+pub mod config;
+pub mod diagnostics;
+mod directives;
+pub mod executables;
+pub mod export;
+pub mod formatting;
+pub mod hover;
+pub mod idle;
+pub mod inlay_hints;
+mod keybind;
+mod parse;
+pub mod paths;
+pub mod references;
+pub mod resolve;
+pub mod schema;
+pub mod stats;
+pub mod template;
+mod themes;
+pub mod workspace;
+
+pub use parse::{Assignment, LineKind, ParsedDocument, ParsedLine};
+
+/// Stands in for a document with no location on disk, so relative paths
+/// aren't resolved.
+const UNTITLED_URI: &str = "untitled:ghostty-config";
+
+/// Completions for zero-based `line` and UTF-16 `col` in `text`, in the order
+/// the server would offer them before ranking and truncation. Some value items
+/// leave their documentation to [`completion::resolve`].
+pub fn get_completions(
+    schema: &GhosttySchema,
+    text: &str,
+    line: u32,
+    col: u32,
+) -> Vec<CompletionItem> {
+    completion::complete(
+        schema,
+        &Config::default(),
+        &ParsedDocument::new(text),
+        Position::new(line, col),
+        None,
+    )
+}
+
+/// Diagnostics for `text` with the default settings.
+pub fn lint(schema: &GhosttySchema, text: &str) -> Vec<Diagnostic> {
+    let uri = Url::parse(UNTITLED_URI).expect("valid placeholder URI");
+    diagnostics::validate_document(
+        schema,
+        &ParsedDocument::new(text),
+        &Config::default(),
+        &ExecutableCache::default(),
+        &uri,
+    )
+}
@@ -1,371 +1,882 @@
-use serde::Deserialize;
-use std::collections::HashMap;
-use std::sync::RwLock;
-use tower_lsp::jsonrpc::Result;
+use dashmap::DashMap;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Notify;
+use tower_lsp::jsonrpc::{Error, Request, Result};
+use tower_lsp::lsp_types::notification::{LogTrace, Notification};
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer, LspService, Server};
+use tower_service::Service;
 
-const SCHEMA_JSON: &str = include_str!("../../schema/ghostty-config.schema.json");
+use ghostty_lsp::completion::KeyInsert;
+use ghostty_lsp::config::{CompletionDetail, Config, DiagnosticsOn, EnumCompletionStyle};
+use ghostty_lsp::diagnostics::Summary;
+use ghostty_lsp::executables::ExecutableCache;
+use ghostty_lsp::export::StructuredFormat;
+use ghostty_lsp::idle::IdleMonitor;
+use ghostty_lsp::resolve::{self, ResolvedConfig};
+use ghostty_lsp::schema::{self, GhosttySchema};
+use ghostty_lsp::stats::Stats;
+use ghostty_lsp::{
+    code_actions, completion, diagnostics, export, formatting, hover, inlay_hints, paths,
+    references, template, workspace, ParsedDocument,
+};
 
-#[derive(Debug, Deserialize)]
-struct GhosttySchema {
-    options: HashMap<String, ConfigOption>,
-    types: Option<TypeDefinitions>,
-    #[serde(rename = "repeatableKeys")]
-    #[allow(dead_code)]
-    repeatable_keys: Option<Vec<String>>,
-}
+const PREVIEW_PARSE_COMMAND: &str = "ghostty.previewParse";
+const GOTO_FIRST_DIAGNOSTIC_COMMAND: &str = "ghostty.gotoFirstDiagnostic";
+const EXPORT_NORMALIZED_COMMAND: &str = "ghostty.exportNormalized";
+const EXPORT_STRUCTURED_COMMAND: &str = "ghostty.exportStructured";
+const FIND_KEYBINDS_COMMAND: &str = "ghostty.findKeybindsForAction";
+const INSERT_TEMPLATE_COMMAND: &str = "ghostty.insertTemplate";
+const TOGGLE_BOOLEAN_COMMAND: &str = "ghostty.toggleBoolean";
+const TOGGLE_THEME_MODE_COMMAND: &str = "ghostty.toggleThemeMode";
+const STATS_COMMAND: &str = "ghostty.stats";
+const OPEN_DOCS_COMMAND: &str = "ghostty.openDocs";
+const VALIDATE_TEXT_COMMAND: &str = "ghostty.validateText";
 
-#[derive(Debug, Deserialize)]
-struct ConfigOption {
-    #[serde(rename = "type")]
-    option_type: String,
-    description: String,
-    #[serde(default)]
-    repeatable: bool,
-    #[serde(default)]
-    deprecated: bool,
-    #[serde(rename = "enum")]
-    enum_values: Option<Vec<String>>,
-    examples: Option<Vec<String>>,
-    platforms: Option<Vec<String>>,
-}
+const WATCHED_FILES_REGISTRATION_ID: &str = "ghostty-config-files";
+const WATCHED_GLOBS: &[&str] = &[
+    "**/*.ghostty",
+    "**/ghostty/**",
+    "**/com.mitchellh.ghostty/**",
+];
+/// Attached to completion items so the client reports which ones were accepted.
+const COMPLETION_ACCEPTED_COMMAND: &str = "ghostty.completionAccepted";
 
-#[derive(Debug, Deserialize)]
-struct TypeDefinitions {
-    keybind: Option<KeybindType>,
-    color: Option<ColorType>,
-}
+/// How long a document has to be left alone before `ghostty +validate-config`
+/// checks it, and how long that may take.
+const GHOSTTY_VALIDATION_DELAY: Duration = Duration::from_millis(750);
+const GHOSTTY_VALIDATION_TIMEOUT: Duration = Duration::from_secs(5);
 
-#[derive(Debug, Deserialize)]
-struct KeybindType {
-    prefixes: Option<Vec<String>>,
-    modifiers: Option<Vec<String>>,
-    actions: Option<Vec<String>>,
-}
+/// How long `ghostty --version` gets to answer before the newest schema is kept.
+const VERSION_DETECTION_TIMEOUT: Duration = Duration::from_secs(2);
 
-#[derive(Debug, Deserialize)]
-struct ColorType {
-    #[serde(rename = "namedValues")]
-    named_values: Option<Vec<String>>,
+/// Set by the extension to the Ghostty config directory it detected.
+const CONFIG_DIR_ENV: &str = "GHOSTTY_LSP_CONFIG_DIR";
+
+/// How long the diagnostics summary waits for more publishes before it's sent,
+/// so a burst of keystrokes or a workspace scan sends one notification.
+const SUMMARY_DELAY: Duration = Duration::from_millis(300);
+
+/// Problem counts across every document, sent with `diagnosticsSummary` on.
+enum DiagnosticsSummary {}
+
+impl Notification for DiagnosticsSummary {
+    type Params = Summary;
+    const METHOD: &'static str = "$/ghostty/diagnosticsSummary";
 }
 
 struct GhosttyLsp {
     client: Client,
-    schema: GhosttySchema,
-    documents: RwLock<HashMap<Url, String>>,
+    /// Chosen in `initialize` to match the user's Ghostty version.
+    schema: RwLock<Arc<GhosttySchema>>,
+    config: RwLock<Config>,
+    executables: ExecutableCache,
+    documents: DashMap<Url, ParsedDocument>,
+    /// Last diagnostics published per document, for the summary code lens and
+    /// notification.
+    published: Arc<DashMap<Url, Vec<Diagnostic>>>,
+    /// Set while a diagnostics summary is waiting to be sent.
+    summary_pending: Arc<AtomicBool>,
+    /// Bumped on each publish, so a pending `ghostty +validate-config` run for
+    /// an older version of the document is dropped.
+    ghostty_runs: Arc<DashMap<Url, u64>>,
+    code_lens_refresh: AtomicBool,
+    /// Whether the client lets us register file watchers after `initialize`.
+    watched_files_registration: AtomicBool,
+    /// Without snippet support, enum values are always offered as plain items.
+    snippet_support: AtomicBool,
+    workspace_folders: RwLock<HashSet<Url>>,
+    /// Request counts and timings, returned by `ghostty.stats`.
+    stats: Stats,
+    /// How often each completion label was accepted this session.
+    /// Keyed by completion context (see [`completion::context_name`]) and label.
+    accepted_completions: Mutex<HashMap<(String, String), u32>>,
+    /// Set by `initialize` and `$/setTrace`; `$/logTrace` is sent unless off.
+    trace: RwLock<TraceValue>,
+    /// Shared with `main`, which stops serving when it reports the server idle.
+    idle: Arc<IdleMonitor>,
+    /// Set by `shutdown`; `main` exits with an error code on an `exit`
+    /// without one, as the protocol asks.
+    shut_down: Arc<AtomicBool>,
 }
 
 impl GhosttyLsp {
-    fn new(client: Client) -> Self {
-        let schema: GhosttySchema =
-            serde_json::from_str(SCHEMA_JSON).expect("Failed to parse embedded schema");
+    fn new(client: Client, idle: Arc<IdleMonitor>, shut_down: Arc<AtomicBool>) -> Self {
+        let schema = RwLock::new(Arc::new(GhosttySchema::load()));
         Self {
             client,
             schema,
-            documents: RwLock::new(HashMap::new()),
+            config: RwLock::new(Config::default()),
+            executables: ExecutableCache::default(),
+            documents: DashMap::new(),
+            published: Arc::new(DashMap::new()),
+            summary_pending: Arc::new(AtomicBool::new(false)),
+            ghostty_runs: Arc::new(DashMap::new()),
+            code_lens_refresh: AtomicBool::new(false),
+            snippet_support: AtomicBool::new(false),
+            watched_files_registration: AtomicBool::new(false),
+            workspace_folders: RwLock::new(HashSet::new()),
+            stats: Stats::default(),
+            accepted_completions: Mutex::new(HashMap::new()),
+            trace: RwLock::new(TraceValue::Off),
+            idle,
+            shut_down,
         }
     }
 
-    fn get_key_completions(&self, partial: &str) -> Vec<CompletionItem> {
-        let partial_lower = partial.to_lowercase();
-        self.schema
-            .options
-            .iter()
-            .filter(|(key, _)| partial.is_empty() || key.to_lowercase().contains(&partial_lower))
-            .map(|(key, opt)| {
-                let detail = self.format_type_detail(opt);
-                let mut item = CompletionItem {
-                    label: key.clone(),
-                    kind: Some(CompletionItemKind::PROPERTY),
-                    detail: Some(detail),
-                    documentation: Some(Documentation::MarkupContent(MarkupContent {
-                        kind: MarkupKind::Markdown,
-                        value: self.format_key_documentation(key, opt),
-                    })),
-                    insert_text: Some(format!("{} = ", key)),
-                    insert_text_format: Some(InsertTextFormat::PLAIN_TEXT),
-                    ..Default::default()
-                };
-                if opt.deprecated {
-                    item.tags = Some(vec![CompletionItemTag::DEPRECATED]);
-                    item.sort_text = Some(format!("z_{}", key));
-                }
-                item
-            })
-            .collect()
+    async fn set_trace(&self, params: SetTraceParams) {
+        *self.trace.write().unwrap() = params.value;
     }
 
-    fn format_type_detail(&self, opt: &ConfigOption) -> String {
-        let mut parts = vec![opt.option_type.clone()];
-        if opt.repeatable {
-            parts.push("repeatable".to_string());
+    /// Sends `$/logTrace` when tracing is on. `verbose` is only built and sent
+    /// at the verbose level.
+    async fn log_trace(&self, message: String, verbose: impl FnOnce() -> String) {
+        let trace = *self.trace.read().unwrap();
+        if trace == TraceValue::Off {
+            return;
         }
-        if let Some(platforms) = &opt.platforms {
-            parts.push(format!("[{}]", platforms.join(", ")));
-        }
-        parts.join(" | ")
+        let verbose = (trace == TraceValue::Verbose).then(verbose);
+        self.client
+            .send_notification::<LogTrace>(LogTraceParams { message, verbose })
+            .await;
     }
 
-    fn format_key_documentation(&self, key: &str, opt: &ConfigOption) -> String {
-        let mut doc = opt.description.clone();
-        if let Some(examples) = &opt.examples {
-            doc.push_str("\n\n**Examples:**\n");
-            for ex in examples.iter().take(3) {
-                doc.push_str(&format!("- `{} = {}`\n", key, ex));
-            }
+    async fn publish_diagnostics(&self, uri: Url, document: &ParsedDocument, version: Option<i32>) {
+        let config = self.config.read().unwrap().clone();
+        let started = Instant::now();
+        let diagnostics = diagnostics::validate_document(
+            &self.schema(),
+            document,
+            &config,
+            &self.executables,
+            &uri,
+        );
+        let elapsed = started.elapsed();
+        self.stats.record("diagnostics", elapsed);
+        self.published.insert(uri.clone(), diagnostics.clone());
+        if config.use_ghostty_validator && !config.safe_mode && self.documents.contains_key(&uri) {
+            self.schedule_ghostty_validation(uri.clone(), document.clone(), version);
         }
-        if let Some(enum_values) = &opt.enum_values {
-            doc.push_str("\n\n**Valid values:** ");
-            doc.push_str(&enum_values.join(", "));
+        self.log_trace(
+            format!("Published {} diagnostics for {}", diagnostics.len(), uri),
+            || format!("Validated in {:?}", elapsed),
+        )
+        .await;
+        self.client
+            .publish_diagnostics(uri, diagnostics, version)
+            .await;
+
+        if self.code_lens_refresh.load(Ordering::Relaxed) {
+            let _ = self.client.code_lens_refresh().await;
         }
-        doc
+        self.schedule_summary();
     }
 
-    fn get_value_completions(&self, key: &str, partial: &str) -> Vec<CompletionItem> {
-        let Some(opt) = self.schema.options.get(key) else {
-            return vec![];
+    /// Runs `ghostty +validate-config` once the document has been left alone
+    /// for [`GHOSTTY_VALIDATION_DELAY`], then publishes what it reports
+    /// alongside the schema checks. It runs in the background, so completion
+    /// and hover never wait on the process.
+    fn schedule_ghostty_validation(
+        &self,
+        uri: Url,
+        document: ParsedDocument,
+        version: Option<i32>,
+    ) {
+        let run = {
+            let mut latest = self.ghostty_runs.entry(uri.clone()).or_default();
+            *latest += 1;
+            *latest
         };
+        let runs = self.ghostty_runs.clone();
+        let client = self.client.clone();
+        let published = self.published.clone();
+        let summary = self
+            .config
+            .read()
+            .unwrap()
+            .diagnostics_summary
+            .then(|| self.summary_pending.clone());
+        let code_lens_refresh = self.code_lens_refresh.load(Ordering::Relaxed);
 
-        let partial_lower = partial.to_lowercase().trim().to_string();
+        tokio::spawn(async move {
+            let is_latest = || runs.get(&uri).is_some_and(|latest| *latest == run);
+            tokio::time::sleep(GHOSTTY_VALIDATION_DELAY).await;
+            if !is_latest() {
+                return;
+            }
+            let Some(found) =
+                diagnostics::ghostty_validate(&document, GHOSTTY_VALIDATION_TIMEOUT).await
+            else {
+                return;
+            };
+            if !is_latest() || found.is_empty() {
+                return;
+            }
+            // Gone when the document was closed in the meantime
+            let diagnostics = {
+                let Some(mut current) = published.get_mut(&uri) else {
+                    return;
+                };
+                current.retain(|d| !diagnostics::is_from_ghostty(d));
+                current.extend(found);
+                current.clone()
+            };
+            client.publish_diagnostics(uri, diagnostics, version).await;
+            if code_lens_refresh {
+                let _ = client.code_lens_refresh().await;
+            }
+            if let Some(pending) = summary {
+                send_summary_later(client, published, pending);
+            }
+        });
+    }
 
-        match opt.option_type.as_str() {
-            "boolean" => self.get_boolean_completions(&partial_lower),
-            "enum" => self.get_enum_completions(opt, &partial_lower),
-            "color" => self.get_colour_completions(&partial_lower),
-            "keybind" => self.get_keybind_completions(&partial_lower),
-            "theme" => self.get_theme_completions(&partial_lower),
-            _ => self.get_example_completions(opt, &partial_lower),
+    /// Sends `$/ghostty/diagnosticsSummary` after [`SUMMARY_DELAY`], unless one
+    /// is already waiting; that one picks up whatever was published since.
+    fn schedule_summary(&self) {
+        if self.config.read().unwrap().diagnostics_summary {
+            send_summary_later(
+                self.client.clone(),
+                self.published.clone(),
+                self.summary_pending.clone(),
+            );
         }
     }
 
-    fn get_boolean_completions(&self, partial: &str) -> Vec<CompletionItem> {
-        ["true", "false"]
-            .iter()
-            .filter(|v| partial.is_empty() || v.contains(partial))
-            .map(|v| self.simple_completion(v, CompletionItemKind::VALUE))
-            .collect()
+    async fn goto_first_diagnostic(&self, arguments: &[Value]) -> Result<Option<Value>> {
+        let uri = document_uri_argument(arguments)
+            .ok_or_else(|| Error::invalid_params("Expected a document URI argument"))?;
+
+        let first = self.published.get(&uri).and_then(|diagnostics| {
+            diagnostics
+                .iter()
+                .map(|d| d.range)
+                .min_by_key(|r| (r.start.line, r.start.character))
+        });
+        let Some(range) = first else {
+            return Ok(None);
+        };
+
+        // Clients without `window/showDocument` can still navigate using the returned location
+        let _ = self
+            .client
+            .show_document(ShowDocumentParams {
+                uri: uri.clone(),
+                external: Some(false),
+                take_focus: Some(true),
+                selection: Some(range),
+            })
+            .await;
+        Ok(serde_json::to_value(Location::new(uri, range)).ok())
     }
 
-    fn get_enum_completions(&self, opt: &ConfigOption, partial: &str) -> Vec<CompletionItem> {
-        opt.enum_values
-            .as_ref()
-            .map(|vals| {
-                vals.iter()
-                    .filter(|v| partial.is_empty() || v.to_lowercase().contains(partial))
-                    .map(|v| self.simple_completion(v, CompletionItemKind::ENUM_MEMBER))
-                    .collect()
+    /// Asks the client to report changes to Ghostty configs and the files next
+    /// to them, which is where includes usually live.
+    async fn register_file_watchers(&self) {
+        let watchers = WATCHED_GLOBS
+            .iter()
+            .map(|glob| FileSystemWatcher {
+                glob_pattern: GlobPattern::String(glob.to_string()),
+                kind: None,
             })
-            .unwrap_or_default()
+            .collect();
+        let registration = Registration {
+            id: WATCHED_FILES_REGISTRATION_ID.to_string(),
+            method: "workspace/didChangeWatchedFiles".to_string(),
+            register_options: serde_json::to_value(DidChangeWatchedFilesRegistrationOptions {
+                watchers,
+            })
+            .ok(),
+        };
+        if let Err(e) = self.client.register_capability(vec![registration]).await {
+            self.client
+                .log_message(
+                    MessageType::WARNING,
+                    format!("Couldn't watch config files: {}", e),
+                )
+                .await;
+        }
     }
 
-    fn get_colour_completions(&self, partial: &str) -> Vec<CompletionItem> {
-        let mut items: Vec<CompletionItem> = vec![];
+    /// Publishes diagnostics for config files in the workspace folders that
+    /// aren't open in the editor.
+    async fn scan_workspace(&self) {
+        let config = self.config.read().unwrap().clone();
+        if !config.workspace_diagnostics
+            || config.safe_mode
+            || config.diagnostics_on == DiagnosticsOn::Off
+        {
+            return;
+        }
 
-        // Named colours from schema
-        if let Some(types) = &self.schema.types {
-            if let Some(color_type) = &types.color {
-                if let Some(named) = &color_type.named_values {
-                    for name in named {
-                        if partial.is_empty() || name.to_lowercase().contains(partial) {
-                            items.push(self.simple_completion(name, CompletionItemKind::COLOR));
-                        }
-                    }
+        let mut roots: Vec<PathBuf> = self
+            .workspace_folders
+            .read()
+            .unwrap()
+            .iter()
+            .filter_map(|folder| folder.to_file_path().ok())
+            .collect();
+        // The extension passes the config directory it detected for the worktree
+        if let Some(dir) = std::env::var_os(CONFIG_DIR_ENV).map(PathBuf::from) {
+            if dir.is_dir() && !roots.iter().any(|root| dir.starts_with(root)) {
+                roots.push(dir);
+            }
+        }
+
+        for root in roots {
+            for path in workspace::discover_config_files(&root, &config.exclude_globs) {
+                let Ok(uri) = Url::from_file_path(&path) else {
+                    continue;
+                };
+                if self.documents.contains_key(&uri) {
+                    continue;
+                }
+                if let Ok(text) = std::fs::read_to_string(&path) {
+                    self.publish_diagnostics(uri, &ParsedDocument::new(text), None)
+                        .await;
                 }
             }
         }
+    }
 
-        // Hex colour template
-        if partial.is_empty() || "#".contains(partial) || partial.starts_with('#') {
-            let mut hex_item = self.simple_completion("#RRGGBB", CompletionItemKind::COLOR);
-            hex_item.detail = Some("Hex colour".to_string());
-            hex_item.insert_text = Some("#".to_string());
-            items.push(hex_item);
+    /// Switches to the schema for the installed Ghostty, unless a version is
+    /// configured, and re-validates whatever was opened in the meantime.
+    async fn detect_schema(&self) {
+        let config = self.config.read().unwrap().clone();
+        if config.ghostty_version.is_some() || config.safe_mode {
+            return;
         }
+        let Some(version) = schema::detect_ghostty_version(VERSION_DETECTION_TIMEOUT).await else {
+            return;
+        };
+        *self.schema.write().unwrap() = Arc::new(GhosttySchema::for_version(Some(&version)));
+        self.republish_open_documents().await;
+    }
 
-        items
+    async fn log_config_problems(&self, problems: Vec<String>) {
+        for problem in problems {
+            self.client.log_message(MessageType::WARNING, problem).await;
+        }
     }
 
-    fn get_keybind_completions(&self, partial: &str) -> Vec<CompletionItem> {
-        let mut items: Vec<CompletionItem> = vec![];
+    /// Validates every open document again, or clears their diagnostics when
+    /// diagnostics are off.
+    async fn republish_open_documents(&self) {
+        let open: Vec<(Url, ParsedDocument)> = self
+            .documents
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+        let off = self.diagnostics_on() == DiagnosticsOn::Off;
+        for (uri, document) in open {
+            if off {
+                self.published.remove(&uri);
+                self.client.publish_diagnostics(uri, vec![], None).await;
+            } else {
+                self.publish_diagnostics(uri, &document, None).await;
+            }
+        }
+        if off {
+            self.schedule_summary();
+        }
+    }
 
-        if let Some(types) = &self.schema.types {
-            if let Some(keybind) = &types.keybind {
-                // Prefixes (global:, all:, etc.)
-                if let Some(prefixes) = &keybind.prefixes {
-                    for prefix in prefixes {
-                        let label = format!("{}:", prefix);
-                        if partial.is_empty() || label.to_lowercase().contains(partial) {
-                            let mut item =
-                                self.simple_completion(&label, CompletionItemKind::KEYWORD);
-                            item.detail = Some("Keybind prefix".to_string());
-                            items.push(item);
-                        }
-                    }
-                }
+    fn schema(&self) -> Arc<GhosttySchema> {
+        self.schema.read().unwrap().clone()
+    }
 
-                // Modifiers (ctrl+, alt+, etc.)
-                if let Some(modifiers) = &keybind.modifiers {
-                    for modifier in modifiers {
-                        let label = format!("{}+", modifier);
-                        if partial.is_empty() || label.to_lowercase().contains(partial) {
-                            let mut item =
-                                self.simple_completion(&label, CompletionItemKind::KEYWORD);
-                            item.detail = Some("Modifier key".to_string());
-                            items.push(item);
-                        }
-                    }
-                }
+    fn diagnostics_on(&self) -> DiagnosticsOn {
+        self.config.read().unwrap().diagnostics_on
+    }
+
+    /// Copies the text out so no shard lock is held across an `.await`.
+    fn document_text(&self, uri: &Url) -> Option<String> {
+        self.documents.get(uri).map(|doc| doc.text().to_string())
+    }
+
+    /// Like [`Self::document_text`], keeping the parsed lines.
+    fn document(&self, uri: &Url) -> Option<ParsedDocument> {
+        self.documents.get(uri).map(|doc| doc.clone())
+    }
+
+    /// Resolves the document named by the command arguments, logging any
+    /// include errors.
+    async fn resolve_document(&self, arguments: &[Value]) -> Result<ResolvedConfig> {
+        let uri = document_uri_argument(arguments)
+            .ok_or_else(|| Error::invalid_params("Expected a document URI argument"))?;
+
+        let Some(content) = self.document_text(&uri) else {
+            return Err(Error::invalid_params(format!(
+                "No document content for {}",
+                uri
+            )));
+        };
 
-                // Actions (after =)
-                if partial.contains('=') || partial.is_empty() {
-                    if let Some(actions) = &keybind.actions {
-                        let after_eq = partial.split('=').last().unwrap_or("").trim();
-                        for action in actions {
-                            if after_eq.is_empty() || action.to_lowercase().contains(after_eq) {
-                                let mut item =
-                                    self.simple_completion(action, CompletionItemKind::FUNCTION);
-                                item.detail = Some("Keybind action".to_string());
-                                items.push(item);
-                            }
-                        }
-                    }
+        let path = uri.to_file_path().ok();
+        let follow_includes = !self.config.read().unwrap().safe_mode;
+        let resolved =
+            resolve::resolve_config(&self.schema(), &content, path.as_deref(), follow_includes);
+        for error in &resolved.errors {
+            self.client.log_message(MessageType::WARNING, error).await;
+        }
+        Ok(resolved)
+    }
+
+    async fn preview_parse(&self, arguments: &[Value]) -> Result<Option<Value>> {
+        let resolved = self.resolve_document(arguments).await?;
+        serde_json::to_value(&resolved.values)
+            .map(Some)
+            .map_err(|e| Error::invalid_params(e.to_string()))
+    }
+
+    async fn export_normalized(&self, arguments: &[Value]) -> Result<Option<Value>> {
+        let resolved = self.resolve_document(arguments).await?;
+        Ok(Some(Value::String(export::normalized(
+            &self.schema(),
+            &resolved,
+        ))))
+    }
+
+    /// Arguments are the document URI and the format, `json` or `toml`; the
+    /// result is the effective configuration serialised in that format.
+    async fn export_structured(&self, arguments: &[Value]) -> Result<Option<Value>> {
+        let format = arguments
+            .get(1)
+            .and_then(Value::as_str)
+            .ok_or_else(|| Error::invalid_params("Expected a format argument"))?;
+        let format = StructuredFormat::parse(format).ok_or_else(|| {
+            Error::invalid_params(format!("Unknown format {}, expected json or toml", format))
+        })?;
+
+        let resolved = self.resolve_document(arguments).await?;
+        export::structured(&resolved, format)
+            .map(|text| Some(Value::String(text)))
+            .map_err(Error::invalid_params)
+    }
+
+    /// Arguments are the document URI and the action name; the result lists the
+    /// `keybind` lines bound to that action.
+    async fn find_keybinds_for_action(&self, arguments: &[Value]) -> Result<Option<Value>> {
+        let uri = document_uri_argument(arguments)
+            .ok_or_else(|| Error::invalid_params("Expected a document URI argument"))?;
+        let action = arguments
+            .get(1)
+            .and_then(Value::as_str)
+            .ok_or_else(|| Error::invalid_params("Expected an action name argument"))?;
+        let Some(document) = self.document(&uri) else {
+            return Err(Error::invalid_params(format!(
+                "No document content for {}",
+                uri
+            )));
+        };
+
+        let locations: Vec<Location> =
+            references::bindings_for_action(&self.schema(), &document, action)
+                .into_iter()
+                .map(|range| Location::new(uri.clone(), range))
+                .collect();
+        Ok(serde_json::to_value(locations).ok())
+    }
+
+    /// Inserts the starter config at the top of the document named by the
+    /// arguments, and returns the edit for clients that apply it themselves.
+    async fn insert_template(&self, arguments: &[Value]) -> Result<Option<Value>> {
+        let uri = document_uri_argument(arguments)
+            .ok_or_else(|| Error::invalid_params("Expected a document URI argument"))?;
+        let template = template::starter_config(&self.schema());
+        let edit = code_actions::template_edit(&uri, template);
+        let _ = self.client.apply_edit(edit.clone()).await;
+        Ok(serde_json::to_value(edit).ok())
+    }
+
+    /// Arguments are the document URI and the cursor position. Flips the
+    /// boolean set on that line and returns the edit, or null when the line
+    /// doesn't set a boolean option.
+    async fn toggle_boolean(&self, arguments: &[Value]) -> Result<Option<Value>> {
+        let uri = document_uri_argument(arguments)
+            .ok_or_else(|| Error::invalid_params("Expected a document URI argument"))?;
+        let position: Position = arguments
+            .get(1)
+            .and_then(|p| serde_json::from_value(p.clone()).ok())
+            .ok_or_else(|| Error::invalid_params("Expected a position argument"))?;
+        let Some(text) = self.document_text(&uri) else {
+            return Err(Error::invalid_params(format!(
+                "No document content for {}",
+                uri
+            )));
+        };
+
+        let Some(edit) = code_actions::toggle_boolean(&self.schema(), &uri, &text, position.line)
+        else {
+            return Ok(None);
+        };
+        let _ = self.client.apply_edit(edit.clone()).await;
+        Ok(serde_json::to_value(edit).ok())
+    }
+
+    /// Takes a document URI, a position and optionally `"light"` to keep the
+    /// light theme when collapsing a pair.
+    async fn toggle_theme_mode(&self, arguments: &[Value]) -> Result<Option<Value>> {
+        let uri = document_uri_argument(arguments)
+            .ok_or_else(|| Error::invalid_params("Expected a document URI argument"))?;
+        let position: Position = arguments
+            .get(1)
+            .and_then(|p| serde_json::from_value(p.clone()).ok())
+            .ok_or_else(|| Error::invalid_params("Expected a position argument"))?;
+        let keep_light = match arguments.get(2).and_then(Value::as_str) {
+            None | Some("dark") => false,
+            Some("light") => true,
+            Some(other) => {
+                return Err(Error::invalid_params(format!(
+                    "Expected `light` or `dark`, got `{}`",
+                    other
+                )))
+            }
+        };
+        let Some(text) = self.document_text(&uri) else {
+            return Err(Error::invalid_params(format!(
+                "No document content for {}",
+                uri
+            )));
+        };
+
+        let Some(edit) = code_actions::toggle_theme_mode(&uri, &text, position.line, keep_light)
+        else {
+            return Ok(None);
+        };
+        let _ = self.client.apply_edit(edit.clone()).await;
+        Ok(serde_json::to_value(edit).ok())
+    }
+
+    /// Returns the documentation URL for an option, named directly or by a
+    /// document URI and position on its line, and asks the client to open it.
+    async fn open_docs(&self, arguments: &[Value]) -> Result<Option<Value>> {
+        let schema = self.schema();
+        let key = match arguments.first().and_then(Value::as_str) {
+            Some(key) if schema.options.contains_key(key) => key.to_string(),
+            _ => {
+                let uri = document_uri_argument(arguments).ok_or_else(|| {
+                    Error::invalid_params("Expected an option name or a document URI argument")
+                })?;
+                let position: Position = arguments
+                    .get(1)
+                    .and_then(|p| serde_json::from_value(p.clone()).ok())
+                    .ok_or_else(|| Error::invalid_params("Expected a position argument"))?;
+                let Some(text) = self.document_text(&uri) else {
+                    return Err(Error::invalid_params(format!(
+                        "No document content for {}",
+                        uri
+                    )));
+                };
+                match code_actions::key_on_line(&schema, &text, position.line) {
+                    Some(key) => key.to_string(),
+                    None => return Ok(None),
                 }
             }
+        };
+
+        let url = schema::docs_url(&key);
+        if let Ok(uri) = Url::parse(&url) {
+            // Clients without `window/showDocument` can open the returned URL themselves
+            let _ = self
+                .client
+                .show_document(ShowDocumentParams {
+                    uri,
+                    external: Some(true),
+                    take_focus: None,
+                    selection: None,
+                })
+                .await;
         }
+        Ok(Some(Value::String(url)))
+    }
 
-        items
-    }
-
-    fn get_theme_completions(&self, partial: &str) -> Vec<CompletionItem> {
-        let themes = [
-            "auto",
-            "Catppuccin Mocha",
-            "Catppuccin Macchiato",
-            "Catppuccin Frappe",
-            "Catppuccin Latte",
-            "Dracula",
-            "Gruvbox Dark",
-            "Gruvbox Light",
-            "Nord",
-            "One Dark",
-            "Solarized Dark",
-            "Solarized Light",
-            "Tokyo Night",
-            "Tokyo Night Storm",
-            "Tomorrow Night",
-        ];
-
-        let mut items: Vec<CompletionItem> = themes
-            .iter()
-            .filter(|t| partial.is_empty() || t.to_lowercase().contains(partial))
-            .map(|t| {
-                let mut item = self.simple_completion(t, CompletionItemKind::VALUE);
-                item.detail = Some("Built-in theme".to_string());
-                item
-            })
-            .collect();
+    /// Lints config text passed as the argument, with no document behind it,
+    /// using the default settings.
+    fn validate_text(&self, arguments: &[Value]) -> Result<Option<Value>> {
+        let text = arguments
+            .first()
+            .and_then(Value::as_str)
+            .ok_or_else(|| Error::invalid_params("Expected config text as the argument"))?;
+        let diagnostics = ghostty_lsp::lint(&self.schema(), text);
+        Ok(serde_json::to_value(diagnostics).ok())
+    }
 
-        // Light/dark combo snippet
-        if partial.is_empty() || "light:".contains(partial) {
-            let mut combo = CompletionItem {
-                label: "light:...,dark:...".to_string(),
-                kind: Some(CompletionItemKind::SNIPPET),
-                detail: Some("Light/dark theme combination".to_string()),
-                insert_text: Some("light:${1:Catppuccin Latte},dark:${2:Catppuccin Mocha}".to_string()),
-                insert_text_format: Some(InsertTextFormat::SNIPPET),
-                ..Default::default()
-            };
-            combo.documentation = Some(Documentation::String(
-                "Use different themes for light and dark mode".to_string(),
+    /// Caps the list at `maxCompletions`, flagging it incomplete so the client
+    /// re-queries as the user narrows the partial.
+    fn completion_response(
+        &self,
+        mut items: Vec<CompletionItem>,
+        context: &str,
+    ) -> CompletionResponse {
+        let config = self.config.read().unwrap().clone();
+        if config.rank_recent_completions {
+            self.rank_by_acceptance(&mut items, context);
+        }
+        if config.completion_detail == CompletionDetail::Minimal {
+            completion::strip_detail(&mut items);
+        }
+
+        let max = config.max_completions;
+        if items.len() <= max {
+            return CompletionResponse::Array(items);
+        }
+        items.truncate(max);
+        CompletionResponse::List(CompletionList {
+            is_incomplete: true,
+            items,
+        })
+    }
+
+    /// Moves previously accepted items to the front and sorts them by how often
+    /// they were picked. Every item reports back through a command when accepted.
+    fn rank_by_acceptance(&self, items: &mut [CompletionItem], context: &str) {
+        let accepted = self.accepted_completions.lock().unwrap();
+        for item in items.iter_mut() {
+            if let Some(count) = accepted.get(&(context.to_string(), item.label.clone())) {
+                let base = item.sort_text.as_deref().unwrap_or(&item.label);
+                item.sort_text = Some(format!("!{:04}{}", 9999 - (*count).min(9999), base));
+            }
+            item.command = Some(Command::new(
+                String::new(),
+                COMPLETION_ACCEPTED_COMMAND.to_string(),
+                Some(vec![
+                    Value::String(item.label.clone()),
+                    Value::String(context.to_string()),
+                ]),
             ));
-            items.push(combo);
         }
+        drop(accepted);
 
-        items
+        // Boosted items sort first, so they also survive truncation
+        items.sort_by_key(|item| {
+            !item
+                .sort_text
+                .as_deref()
+                .is_some_and(|s| s.starts_with('!'))
+        });
     }
 
-    fn get_example_completions(&self, opt: &ConfigOption, partial: &str) -> Vec<CompletionItem> {
-        opt.examples
-            .as_ref()
-            .map(|examples| {
-                examples
-                    .iter()
-                    .filter(|ex| partial.is_empty() || ex.to_lowercase().contains(partial))
-                    .map(|ex| {
-                        let mut item = self.simple_completion(ex, CompletionItemKind::VALUE);
-                        item.detail = Some("Example value".to_string());
-                        item
-                    })
-                    .collect()
-            })
-            .unwrap_or_default()
+    fn find_references(&self, params: ReferenceParams) -> Option<Vec<Location>> {
+        let uri = params.text_document_position.text_document.uri;
+        let document = self.document(&uri)?;
+        let schema = self.schema();
+        let position = params.text_document_position.position;
+        let action = references::action_at(&schema, &document, position)?;
+
+        let locations = references::bindings_for_action(&schema, &document, &action)
+            .into_iter()
+            .map(|range| Location::new(uri.clone(), range))
+            .collect();
+        Some(locations)
     }
 
-    fn simple_completion(&self, label: &str, kind: CompletionItemKind) -> CompletionItem {
-        CompletionItem {
-            label: label.to_string(),
-            kind: Some(kind),
-            ..Default::default()
+    /// Takes the accepted label and its completion context.
+    fn record_accepted_completion(&self, arguments: &[Value]) -> Result<Option<Value>> {
+        let label = arguments.first().and_then(Value::as_str);
+        let context = arguments.get(1).and_then(Value::as_str).unwrap_or("key");
+        if let Some(label) = label {
+            *self
+                .accepted_completions
+                .lock()
+                .unwrap()
+                .entry((context.to_string(), label.to_string()))
+                .or_default() += 1;
         }
+        Ok(None)
     }
 
-    fn parse_line_context(&self, line: &str, character: u32) -> LineContext {
-        let char_pos = character as usize;
-        let trimmed = line.trim_start();
-
-        // Skip comments
-        if trimmed.starts_with('#') {
-            return LineContext::Comment;
-        }
+    async fn complete(&self, params: CompletionParams) -> CompletionResponse {
+        let uri = &params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
 
-        // Find equals position
-        if let Some(eq_pos) = line.find('=') {
-            if char_pos <= eq_pos {
-                // Cursor is before or at equals - completing key
-                let key_part = &line[..char_pos];
-                LineContext::Key(key_part.trim().to_string())
-            } else {
-                // Cursor is after equals - completing value
-                let key = line[..eq_pos].trim().to_string();
-                let value_part = &line[eq_pos + 1..char_pos];
-                LineContext::Value {
-                    key,
-                    partial: value_part.trim_start().to_string(),
+        // Completion can race ahead of didOpen, or target a document the client
+        // never opened; the file on disk is the best stand-in for its text
+        let safe_mode = self.config.read().unwrap().safe_mode;
+        let document = match self.document(uri) {
+            Some(document) => document,
+            None => match uri
+                .to_file_path()
+                .ok()
+                .filter(|_| !safe_mode)
+                .and_then(|p| std::fs::read_to_string(p).ok())
+            {
+                Some(content) => {
+                    self.client
+                        .log_message(
+                            MessageType::LOG,
+                            format!("{} is not open yet; completing from the file on disk", uri),
+                        )
+                        .await;
+                    ParsedDocument::new(content)
                 }
-            }
-        } else {
-            // No equals - completing key
-            let key_part = &line[..char_pos.min(line.len())];
-            LineContext::Key(key_part.trim().to_string())
+                None => {
+                    self.client
+                        .log_message(MessageType::LOG, format!("No document content for {}", uri))
+                        .await;
+                    // Fallback: return all key completions
+                    let insert = KeyInsert::for_config(&self.config.read().unwrap());
+                    let items = completion::get_key_completions(&self.schema(), "", None, insert);
+                    return self.completion_response(items, "key");
+                }
+            },
+        };
+
+        let base_dir = paths::document_dir(uri);
+        let mut config = self.config.read().unwrap().clone();
+        if !self.snippet_support.load(Ordering::Relaxed) {
+            config.enum_completion_style = EnumCompletionStyle::Items;
         }
+        let items = completion::complete(
+            &self.schema(),
+            &config,
+            &document,
+            position,
+            base_dir.as_deref(),
+        );
+        let context = completion::context_name(&document, position);
+        self.completion_response(items, &context)
     }
 }
 
-#[derive(Debug)]
-enum LineContext {
-    Comment,
-    Key(String),
-    Value { key: String, partial: String },
+/// Sends the diagnostics summary once [`SUMMARY_DELAY`] has passed.
+fn send_summary_later(
+    client: Client,
+    published: Arc<DashMap<Url, Vec<Diagnostic>>>,
+    pending: Arc<AtomicBool>,
+) {
+    if pending.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    tokio::spawn(async move {
+        tokio::time::sleep(SUMMARY_DELAY).await;
+        pending.store(false, Ordering::Relaxed);
+        let mut summary = Summary::default();
+        for entry in published.iter() {
+            summary.add(entry.value());
+        }
+        client
+            .send_notification::<DiagnosticsSummary>(summary)
+            .await;
+    });
+}
+
+/// Accepts either a bare URI string or a `TextDocumentIdentifier`-shaped object.
+fn document_uri_argument(arguments: &[Value]) -> Option<Url> {
+    let arg = arguments.first()?;
+    let raw = arg
+        .as_str()
+        .or_else(|| arg.get("uri").and_then(Value::as_str))?;
+    Url::parse(raw).ok()
 }
 
 #[tower_lsp::async_trait]
 impl LanguageServer for GhosttyLsp {
-    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+        let (config, problems) = Config::parse(params.initialization_options);
+        self.log_config_problems(problems).await;
+        *self.trace.write().unwrap() = config.trace.or(params.trace).unwrap_or(TraceValue::Off);
+        // Without a configured version, `initialized` asks `ghostty` for one
+        *self.schema.write().unwrap() = Arc::new(GhosttySchema::for_version(
+            config.ghostty_version.as_deref(),
+        ));
+        *self.config.write().unwrap() = config;
+
+        let code_lens_refresh = params
+            .capabilities
+            .workspace
+            .as_ref()
+            .and_then(|w| w.code_lens.as_ref())
+            .and_then(|c| c.refresh_support)
+            .unwrap_or(false);
+        self.code_lens_refresh
+            .store(code_lens_refresh, Ordering::Relaxed);
+
+        let snippet_support = params
+            .capabilities
+            .text_document
+            .as_ref()
+            .and_then(|t| t.completion.as_ref())
+            .and_then(|c| c.completion_item.as_ref())
+            .and_then(|i| i.snippet_support)
+            .unwrap_or(false);
+        self.snippet_support
+            .store(snippet_support, Ordering::Relaxed);
+
+        let watched_files_registration = params
+            .capabilities
+            .workspace
+            .as_ref()
+            .and_then(|w| w.did_change_watched_files.as_ref())
+            .and_then(|w| w.dynamic_registration)
+            .unwrap_or(false);
+        self.watched_files_registration
+            .store(watched_files_registration, Ordering::Relaxed);
+
+        #[allow(deprecated)]
+        let folders: HashSet<Url> = match params.workspace_folders {
+            Some(folders) => folders.into_iter().map(|f| f.uri).collect(),
+            None => params.root_uri.into_iter().collect(),
+        };
+        *self.workspace_folders.write().unwrap() = folders;
+
         Ok(InitializeResult {
             capabilities: ServerCapabilities {
                 text_document_sync: Some(TextDocumentSyncCapability::Options(
                     TextDocumentSyncOptions {
                         open_close: Some(true),
                         change: Some(TextDocumentSyncKind::FULL),
+                        save: Some(TextDocumentSyncSaveOptions::SaveOptions(SaveOptions {
+                            include_text: Some(false),
+                        })),
                         ..Default::default()
                     },
                 )),
                 completion_provider: Some(CompletionOptions {
                     trigger_characters: Some(vec!["=".to_string(), " ".to_string()]),
+                    resolve_provider: Some(true),
+                    ..Default::default()
+                }),
+                document_formatting_provider: Some(OneOf::Left(true)),
+                document_range_formatting_provider: Some(OneOf::Left(true)),
+                code_lens_provider: Some(CodeLensOptions {
                     resolve_provider: Some(false),
+                }),
+                code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+                inlay_hint_provider: Some(OneOf::Left(true)),
+                references_provider: Some(OneOf::Left(true)),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                execute_command_provider: Some(ExecuteCommandOptions {
+                    commands: vec![
+                        PREVIEW_PARSE_COMMAND.to_string(),
+                        GOTO_FIRST_DIAGNOSTIC_COMMAND.to_string(),
+                        EXPORT_NORMALIZED_COMMAND.to_string(),
+                        EXPORT_STRUCTURED_COMMAND.to_string(),
+                        FIND_KEYBINDS_COMMAND.to_string(),
+                        INSERT_TEMPLATE_COMMAND.to_string(),
+                        TOGGLE_BOOLEAN_COMMAND.to_string(),
+                        TOGGLE_THEME_MODE_COMMAND.to_string(),
+                        OPEN_DOCS_COMMAND.to_string(),
+                        VALIDATE_TEXT_COMMAND.to_string(),
+                        STATS_COMMAND.to_string(),
+                        COMPLETION_ACCEPTED_COMMAND.to_string(),
+                    ],
                     ..Default::default()
                 }),
+                workspace: Some(WorkspaceServerCapabilities {
+                    workspace_folders: Some(WorkspaceFoldersServerCapabilities {
+                        supported: Some(true),
+                        change_notifications: Some(OneOf::Left(true)),
+                    }),
+                    file_operations: None,
+                }),
                 ..Default::default()
             },
             server_info: Some(ServerInfo {
@@ -379,82 +890,404 @@ impl LanguageServer for GhosttyLsp {
         self.client
             .log_message(MessageType::INFO, "Ghostty LSP initialised")
             .await;
+
+        let idle_shutdown_secs = self.config.read().unwrap().idle_shutdown_secs;
+        if let Some(secs) = idle_shutdown_secs.filter(|&secs| secs > 0) {
+            let idle = self.idle.clone();
+            tokio::spawn(async move { idle.watch(Duration::from_secs(secs)).await });
+        }
+
+        self.detect_schema().await;
+
+        if cfg!(debug_assertions) {
+            for problem in schema::validate_schema(&self.schema()) {
+                self.client
+                    .log_message(MessageType::ERROR, format!("Schema problem: {}", problem))
+                    .await;
+            }
+        }
+
+        let safe_mode = self.config.read().unwrap().safe_mode;
+        if self.watched_files_registration.load(Ordering::Relaxed) && !safe_mode {
+            self.register_file_watchers().await;
+        }
+
+        self.scan_workspace().await;
+        // Sent even with nothing published, so a status bar starts out healthy
+        self.schedule_summary();
     }
 
+    /// Clears every published diagnostic, so nothing stale is left in the
+    /// editor if the server isn't started again.
     async fn shutdown(&self) -> Result<()> {
+        self.shut_down.store(true, Ordering::Relaxed);
+        let uris: Vec<Url> = self.published.iter().map(|e| e.key().clone()).collect();
+        self.published.clear();
+        for uri in uris {
+            self.client.publish_diagnostics(uri, vec![], None).await;
+        }
         Ok(())
     }
 
+    /// Re-validates open documents, so settings such as `strict` apply
+    /// without waiting for an edit.
+    async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
+        let (config, problems) = Config::parse(Some(params.settings));
+        self.log_config_problems(problems).await;
+        *self.config.write().unwrap() = config;
+        self.republish_open_documents().await;
+    }
+
+    /// Configs and includes edited outside the editor: re-validate open
+    /// documents that include a changed file, and workspace configs that
+    /// aren't open.
+    async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
+        let config = self.config.read().unwrap().clone();
+        if config.diagnostics_on == DiagnosticsOn::Off {
+            return;
+        }
+
+        let changed: Vec<PathBuf> = params
+            .changes
+            .iter()
+            .filter_map(|event| event.uri.to_file_path().ok())
+            .collect();
+        let open: Vec<(Url, ParsedDocument)> = self
+            .documents
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+        for (uri, document) in open {
+            let includes =
+                resolve::include_paths(document.text(), paths::document_dir(&uri).as_deref());
+            if includes.iter().any(|include| changed.contains(include)) {
+                self.publish_diagnostics(uri, &document, None).await;
+            }
+        }
+
+        if !config.workspace_diagnostics || config.safe_mode {
+            return;
+        }
+        for event in params.changes {
+            let Ok(path) = event.uri.to_file_path() else {
+                continue;
+            };
+            if self.documents.contains_key(&event.uri) || !workspace::is_config_file(&path) {
+                continue;
+            }
+            if event.typ == FileChangeType::DELETED {
+                self.published.remove(&event.uri);
+                self.client
+                    .publish_diagnostics(event.uri, vec![], None)
+                    .await;
+                self.schedule_summary();
+            } else if let Ok(text) = std::fs::read_to_string(&path) {
+                self.publish_diagnostics(event.uri, &ParsedDocument::new(text), None)
+                    .await;
+            }
+        }
+    }
+
+    async fn did_change_workspace_folders(&self, params: DidChangeWorkspaceFoldersParams) {
+        {
+            let mut folders = self.workspace_folders.write().unwrap();
+            for removed in params.event.removed {
+                folders.remove(&removed.uri);
+            }
+            folders.extend(params.event.added.into_iter().map(|f| f.uri));
+        }
+        self.scan_workspace().await;
+    }
+
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
         let uri = params.text_document.uri;
-        let text = params.text_document.text;
-        if let Ok(mut docs) = self.documents.write() {
-            docs.insert(uri, text);
+        let document = ParsedDocument::new(params.text_document.text);
+        let version = params.text_document.version;
+        if self
+            .documents
+            .insert(uri.clone(), document.clone())
+            .is_none()
+        {
+            self.idle.opened();
+        }
+        if self.diagnostics_on() != DiagnosticsOn::Off {
+            self.publish_diagnostics(uri, &document, Some(version))
+                .await;
         }
     }
 
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
         let uri = params.text_document.uri;
+        let version = params.text_document.version;
         if let Some(change) = params.content_changes.into_iter().last() {
-            if let Ok(mut docs) = self.documents.write() {
-                docs.insert(uri, change.text);
+            // Only the lines the edit touched are parsed again. Changes to a
+            // document that was never opened are ignored, since it isn't
+            // counted as open for idle shutdown either
+            let document = {
+                let Some(mut document) = self.documents.get_mut(&uri) else {
+                    return;
+                };
+                document.update(change.text);
+                document.clone()
+            };
+            if self.diagnostics_on() == DiagnosticsOn::Change {
+                self.publish_diagnostics(uri, &document, Some(version))
+                    .await;
             }
         }
     }
 
+    async fn did_save(&self, params: DidSaveTextDocumentParams) {
+        if self.diagnostics_on() != DiagnosticsOn::Save {
+            return;
+        }
+        let uri = params.text_document.uri;
+        if let Some(document) = self.document(&uri) {
+            self.publish_diagnostics(uri, &document, None).await;
+        }
+    }
+
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
-        if let Ok(mut docs) = self.documents.write() {
-            docs.remove(&params.text_document.uri);
+        let uri = params.text_document.uri;
+        if self.documents.remove(&uri).is_some() {
+            self.idle.closed();
         }
+        self.ghostty_runs.remove(&uri);
+        self.published.remove(&uri);
+        self.client.publish_diagnostics(uri, vec![], None).await;
+        self.schedule_summary();
     }
 
     async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
-        let uri = &params.text_document_position.text_document.uri;
-        let position = params.text_document_position.position;
+        let position = params.text_document_position.clone();
+        let started = Instant::now();
+        let response = self.complete(params).await;
+        let elapsed = started.elapsed();
+        self.stats.record("completion", elapsed);
 
-        // Get the document content
-        let content = {
-            let docs = self.documents.read().unwrap();
-            docs.get(uri).cloned()
+        let count = match &response {
+            CompletionResponse::Array(items) => items.len(),
+            CompletionResponse::List(list) => list.items.len(),
         };
+        self.log_trace(
+            format!(
+                "Completion requested at {}:{} in {}",
+                position.position.line, position.position.character, position.text_document.uri
+            ),
+            || format!("Returned {} items in {:?}", count, elapsed),
+        )
+        .await;
+        Ok(Some(response))
+    }
 
-        let Some(content) = content else {
-            self.client
-                .log_message(
-                    MessageType::WARNING,
-                    format!("No document content for {}", uri),
-                )
-                .await;
-            // Fallback: return all key completions
-            return Ok(Some(CompletionResponse::Array(self.get_key_completions(""))));
-        };
+    /// Builds the documentation value items leave out of the list, such as a
+    /// theme's palette preview.
+    async fn completion_resolve(&self, item: CompletionItem) -> Result<CompletionItem> {
+        let config = self.config.read().unwrap().clone();
+        Ok(completion::resolve(&self.schema(), &config, item))
+    }
 
-        // Get the current line
-        let lines: Vec<&str> = content.lines().collect();
-        let line_num = position.line as usize;
-        if line_num >= lines.len() {
-            return Ok(Some(CompletionResponse::Array(self.get_key_completions(""))));
+    async fn formatting(&self, params: DocumentFormattingParams) -> Result<Option<Vec<TextEdit>>> {
+        let started = Instant::now();
+        let edits = self
+            .documents
+            .get(&params.text_document.uri)
+            .map(|doc| formatting::format_document(doc.text()));
+        self.stats.record("formatting", started.elapsed());
+        Ok(edits)
+    }
+
+    async fn range_formatting(
+        &self,
+        params: DocumentRangeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        let started = Instant::now();
+        let edits = self
+            .documents
+            .get(&params.text_document.uri)
+            .map(|doc| formatting::format_range(doc.text(), params.range));
+        self.stats.record("rangeFormatting", started.elapsed());
+        Ok(edits)
+    }
+
+    async fn inlay_hint(&self, params: InlayHintParams) -> Result<Option<Vec<InlayHint>>> {
+        if !self.config.read().unwrap().keybind_action_hints {
+            return Ok(None);
         }
-        let line = lines[line_num];
+        let started = Instant::now();
+        let hints = self
+            .documents
+            .get(&params.text_document.uri)
+            .map(|doc| inlay_hints::keybind_action_hints(&self.schema(), &doc, params.range));
+        self.stats.record("inlayHint", started.elapsed());
+        Ok(hints)
+    }
 
-        // Parse context and get completions
-        let context = self.parse_line_context(line, position.character);
+    async fn code_lens(&self, params: CodeLensParams) -> Result<Option<Vec<CodeLens>>> {
+        let uri = params.text_document.uri;
+        let Some(diagnostics) = self.published.get(&uri).map(|d| d.clone()) else {
+            return Ok(None);
+        };
 
-        let items = match context {
-            LineContext::Comment => vec![],
-            LineContext::Key(partial) => self.get_key_completions(&partial),
-            LineContext::Value { key, partial } => self.get_value_completions(&key, &partial),
+        // Clicking the lens jumps to the first problem, if there is one
+        let (command, arguments) = if diagnostics.is_empty() {
+            (String::new(), None)
+        } else {
+            (
+                GOTO_FIRST_DIAGNOSTIC_COMMAND.to_string(),
+                Some(vec![Value::String(uri.to_string())]),
+            )
         };
+        let command = Command {
+            title: diagnostics::summarize(&diagnostics),
+            command,
+            arguments,
+        };
+
+        Ok(Some(vec![CodeLens {
+            range: Range::default(),
+            command: Some(command),
+            data: None,
+        }]))
+    }
+
+    async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
+        let position = params.text_document_position_params;
+        let started = Instant::now();
+        let hover = self
+            .document(&position.text_document.uri)
+            .and_then(|document| hover::hover(&self.schema(), &document, position.position));
+        self.stats.record("hover", started.elapsed());
+        Ok(hover)
+    }
+
+    /// On a keybind action, lists every binding to that action in the document.
+    async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
+        let started = Instant::now();
+        let locations = self.find_references(params);
+        self.stats.record("references", started.elapsed());
+        Ok(locations)
+    }
+
+    async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        let started = Instant::now();
+        let uri = &params.text_document.uri;
+        let mut actions = code_actions::quick_fixes(uri, &params.context.diagnostics);
+        let text = self.document_text(uri);
+        if text.as_deref().is_some_and(|text| text.trim().is_empty()) {
+            let template = template::starter_config(&self.schema());
+            actions.push(code_actions::insert_template(uri, template));
+        }
+        let schema = self.schema();
+        if let Some(key) = text
+            .as_deref()
+            .and_then(|text| code_actions::key_on_line(&schema, text, params.range.start.line))
+        {
+            actions.push(code_actions::open_docs(key, OPEN_DOCS_COMMAND));
+        }
+        self.stats.record("codeAction", started.elapsed());
+        Ok(Some(actions))
+    }
+
+    async fn execute_command(&self, params: ExecuteCommandParams) -> Result<Option<Value>> {
+        match params.command.as_str() {
+            PREVIEW_PARSE_COMMAND => self.preview_parse(&params.arguments).await,
+            GOTO_FIRST_DIAGNOSTIC_COMMAND => self.goto_first_diagnostic(&params.arguments).await,
+            EXPORT_NORMALIZED_COMMAND => self.export_normalized(&params.arguments).await,
+            EXPORT_STRUCTURED_COMMAND => self.export_structured(&params.arguments).await,
+            FIND_KEYBINDS_COMMAND => self.find_keybinds_for_action(&params.arguments).await,
+            INSERT_TEMPLATE_COMMAND => self.insert_template(&params.arguments).await,
+            TOGGLE_BOOLEAN_COMMAND => self.toggle_boolean(&params.arguments).await,
+            TOGGLE_THEME_MODE_COMMAND => self.toggle_theme_mode(&params.arguments).await,
+            OPEN_DOCS_COMMAND => self.open_docs(&params.arguments).await,
+            VALIDATE_TEXT_COMMAND => self.validate_text(&params.arguments),
+            STATS_COMMAND => Ok(Some(self.stats.to_json())),
+            COMPLETION_ACCEPTED_COMMAND => self.record_accepted_completion(&params.arguments),
+            _ => Err(Error::invalid_params(format!(
+                "Unknown command {}",
+                params.command
+            ))),
+        }
+    }
+}
 
-        Ok(Some(CompletionResponse::Array(items)))
+/// Passes messages through to the server, signalling `exited` when the
+/// `exit` notification arrives. tower-lsp only stops serving once the message
+/// after it is read, which a client that has sent `exit` never writes.
+struct WatchExit<S> {
+    inner: S,
+    exited: Arc<Notify>,
+}
+
+impl<S: Service<Request>> Service<Request> for WatchExit<S> {
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<std::result::Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let exiting = request.method() == "exit";
+        let response = self.inner.call(request);
+        if exiting {
+            self.exited.notify_one();
+        }
+        response
     }
 }
 
+/// Prints the server version and what the embedded schema covers, for bug
+/// reports.
+fn print_version() {
+    let schema = GhosttySchema::load();
+    let (major, minor) = schema::newest_release();
+    println!("ghostty-lsp {}", env!("CARGO_PKG_VERSION"));
+    println!(
+        "schema {} for Ghostty {}.{}, {} options",
+        schema.version.as_deref().unwrap_or("unversioned"),
+        major,
+        minor,
+        schema.options.len()
+    );
+}
+
 #[tokio::main]
 async fn main() {
+    if std::env::args().nth(1).as_deref() == Some("--version") {
+        print_version();
+        return;
+    }
+
     let stdin = tokio::io::stdin();
     let stdout = tokio::io::stdout();
 
-    let (service, socket) = LspService::new(GhosttyLsp::new);
-    Server::new(stdin, stdout, socket).serve(service).await;
+    let idle = Arc::new(IdleMonitor::default());
+    let shut_down = Arc::new(AtomicBool::new(false));
+    // tower-lsp doesn't route `$/setTrace` to the `LanguageServer` trait
+    let (service, socket) =
+        LspService::build(|client| GhosttyLsp::new(client, idle.clone(), shut_down.clone()))
+            .custom_method("$/setTrace", GhosttyLsp::set_trace)
+            .finish();
+
+    let exited = Arc::new(Notify::new());
+    let service = WatchExit {
+        inner: service,
+        exited: exited.clone(),
+    };
+
+    tokio::select! {
+        _ = Server::new(stdin, stdout, socket).serve(service) => {}
+        _ = exited.notified() => {
+            // Returning would wait on the blocking stdin read, so flush what
+            // was written and leave straight away
+            let _ = tokio::io::stdout().flush().await;
+            std::process::exit(if shut_down.load(Ordering::Relaxed) { 0 } else { 1 });
+        }
+        _ = idle.shutdown_requested() => {
+            // Returning would wait on the blocking stdin read until the editor writes again
+            std::process::exit(0);
+        }
+    }
 }
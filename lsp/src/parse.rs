@@ -0,0 +1,400 @@
+use std::ops::Range;
+
+/// A single `key = value` line, with byte offsets into the original line.
+pub struct Assignment<'a> {
+    pub key: &'a str,
+    pub key_start: usize,
+    pub value: &'a str,
+    pub value_start: usize,
+}
+
+impl<'a> Assignment<'a> {
+    /// The assignment as Ghostty reads it, with one pair of double quotes
+    /// around the value removed. See [`unquote`].
+    pub fn unquoted(&self) -> Self {
+        let (offset, value) = unquote(self.value);
+        Self {
+            key: self.key,
+            key_start: self.key_start,
+            value,
+            value_start: self.value_start + offset,
+        }
+    }
+}
+
+/// Ghostty strips a single pair of double quotes around a value, so
+/// `command = "/usr/bin/env fish"` runs `/usr/bin/env fish`. Nothing else is
+/// special: there are no escapes, and single quotes are kept as written.
+/// Returns the value inside the quotes and its byte offset in `value`.
+pub fn unquote(value: &str) -> (usize, &str) {
+    match value
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+    {
+        Some(inner) => (1, inner),
+        None => (0, value),
+    }
+}
+
+pub fn parse_assignment(line: &str) -> Option<Assignment<'_>> {
+    let trimmed = line.trim_start();
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+        return None;
+    }
+
+    let eq_pos = line.find('=')?;
+    let raw_key = &line[..eq_pos];
+    let key = raw_key.trim();
+    let key_start = raw_key.len() - raw_key.trim_start().len();
+
+    let raw_value = &line[eq_pos + 1..];
+    let value = raw_value.trim();
+    let value_start = eq_pos + 1 + (raw_value.len() - raw_value.trim_start().len());
+
+    Some(Assignment {
+        key,
+        key_start,
+        value,
+        value_start,
+    })
+}
+
+/// What a line of a config holds, with byte ranges into the line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineKind {
+    Blank,
+    Comment,
+    /// `key = value`. `eq` is the byte offset of the `=` separating them.
+    Assignment {
+        key: Range<usize>,
+        eq: usize,
+        value: Range<usize>,
+    },
+    /// Anything else, such as a key still being typed with no `=` yet.
+    Other,
+}
+
+impl LineKind {
+    pub fn of(line: &str) -> Self {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() {
+            return Self::Blank;
+        }
+        if trimmed.starts_with('#') {
+            return Self::Comment;
+        }
+        match parse_assignment(line) {
+            Some(assignment) => Self::Assignment {
+                key: assignment.key_start..assignment.key_start + assignment.key.len(),
+                eq: line.find('=').unwrap_or_default(),
+                value: assignment.value_start..assignment.value_start + assignment.value.len(),
+            },
+            None => Self::Other,
+        }
+    }
+}
+
+/// A line of a [`ParsedDocument`], without its line ending.
+#[derive(Debug, Clone)]
+pub struct ParsedLine {
+    pub text: String,
+    pub kind: LineKind,
+}
+
+impl ParsedLine {
+    fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            kind: LineKind::of(text),
+        }
+    }
+
+    pub fn assignment(&self) -> Option<Assignment<'_>> {
+        let LineKind::Assignment { key, value, .. } = &self.kind else {
+            return None;
+        };
+        Some(Assignment {
+            key: &self.text[key.clone()],
+            key_start: key.start,
+            value: &self.text[value.clone()],
+            value_start: value.start,
+        })
+    }
+}
+
+/// A document split into lines, each parsed once. [`ParsedDocument::update`]
+/// only re-parses the lines an edit touched.
+#[derive(Debug, Clone, Default)]
+pub struct ParsedDocument {
+    text: String,
+    lines: Vec<ParsedLine>,
+}
+
+impl ParsedDocument {
+    pub fn new(text: impl Into<String>) -> Self {
+        let text = text.into();
+        let lines = text.lines().map(ParsedLine::new).collect();
+        Self { text, lines }
+    }
+
+    /// Replaces the text, keeping the parses of the unchanged lines before
+    /// and after the edited region.
+    pub fn update(&mut self, text: impl Into<String>) {
+        let text = text.into();
+        let new_lines: Vec<&str> = text.lines().collect();
+        let prefix = self
+            .lines
+            .iter()
+            .zip(&new_lines)
+            .take_while(|(old, new)| old.text == **new)
+            .count();
+        let suffix = self.lines[prefix..]
+            .iter()
+            .rev()
+            .zip(new_lines[prefix..].iter().rev())
+            .take_while(|(old, new)| old.text == **new)
+            .count();
+
+        let changed = &new_lines[prefix..new_lines.len() - suffix];
+        self.lines.splice(
+            prefix..self.lines.len() - suffix,
+            changed.iter().map(|line| ParsedLine::new(line)),
+        );
+        self.text = text;
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn line(&self, line_num: usize) -> Option<&ParsedLine> {
+        self.lines.get(line_num)
+    }
+
+    pub fn lines(&self) -> &[ParsedLine] {
+        &self.lines
+    }
+
+    /// Every `key = value` line, with its line number and text.
+    pub fn assignments(&self) -> impl Iterator<Item = (usize, &str, Assignment<'_>)> {
+        self.lines
+            .iter()
+            .enumerate()
+            .filter_map(|(line_num, line)| Some((line_num, line.text.as_str(), line.assignment()?)))
+    }
+}
+
+/// Splits recognised leading prefixes (`global:`, `unconsumed:`, ...) off a
+/// keybind value, returning them along with the remaining trigger text.
+pub fn strip_keybind_prefixes<'a>(value: &'a str, prefixes: &[String]) -> (Vec<&'a str>, &'a str) {
+    let mut used = vec![];
+    let mut rest = value;
+    while let Some((head, tail)) = rest.split_once(':') {
+        if !prefixes.iter().any(|p| p.eq_ignore_ascii_case(head)) {
+            break;
+        }
+        used.push(head);
+        rest = tail;
+    }
+    (used, rest)
+}
+
+/// Splits a keybind value into its trigger and action at the `=` that ends the
+/// trigger. Only the first `=` on the line separates key from value, but the
+/// keybind value itself contains further `=`: the trigger may bind the `=` key
+/// (`ctrl+==increase_font_size`) and action arguments may contain `=`
+/// (`ctrl+a=text:a=b`). An `=` at the start of a key, after a `+`, `>` or `:`
+/// separator, is the key itself, so the split is the first `=` that isn't. A
+/// separator that is itself a key doesn't count: `ctrl++=x` binds `+`.
+pub fn split_keybind(value: &str) -> Option<(&str, &str)> {
+    let mut key_start = true;
+    for (i, byte) in value.bytes().enumerate() {
+        if byte == b'=' && !key_start {
+            return Some((&value[..i], &value[i + 1..]));
+        }
+        key_start = matches!(byte, b'+' | b'>' | b':') && !key_start;
+    }
+    None
+}
+
+/// The feature a `flags` list entry names, without its `no-` prefix.
+pub fn flag_name(entry: &str) -> &str {
+    let entry = entry.trim();
+    entry.strip_prefix("no-").unwrap_or(entry)
+}
+
+/// LSP positions are measured in UTF-16 code units, not bytes.
+pub fn utf16_col(line: &str, byte_idx: usize) -> u32 {
+    line[..byte_idx].encode_utf16().count() as u32
+}
+
+/// The byte index of UTF-16 column `col`, clamped to the end of the line.
+pub fn byte_offset(line: &str, col: u32) -> usize {
+    let mut units = 0;
+    for (i, c) in line.char_indices() {
+        if units >= col as usize {
+            return i;
+        }
+        units += c.len_utf16();
+    }
+    line.len()
+}
+
+/// How much of a document is sampled to decide whether it's text.
+const TEXT_SAMPLE_CHARS: usize = 8192;
+
+/// False for content that's clearly not a text file, such as a binary opened
+/// with the Ghostty language: it contains a NUL, or more than a third of the
+/// sample is control characters or undecodable bytes.
+pub fn looks_like_text(text: &str) -> bool {
+    let mut total = 0;
+    let mut suspicious = 0;
+    for c in text.chars().take(TEXT_SAMPLE_CHARS) {
+        if c == '\0' {
+            return false;
+        }
+        total += 1;
+        if (c.is_control() && !matches!(c, '\t' | '\n' | '\r')) || c == char::REPLACEMENT_CHARACTER
+        {
+            suspicious += 1;
+        }
+    }
+    suspicious * 3 <= total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_first_equals_separates_key_and_value() {
+        let assignment = parse_assignment("keybind = ctrl+a=text:a=b").unwrap();
+        assert_eq!(assignment.key, "keybind");
+        assert_eq!(assignment.value, "ctrl+a=text:a=b");
+    }
+
+    #[test]
+    fn keybind_splits_after_the_trigger() {
+        assert_eq!(
+            split_keybind("ctrl+a=text:a=b"),
+            Some(("ctrl+a", "text:a=b"))
+        );
+    }
+
+    #[test]
+    fn keybind_can_bind_the_equals_key() {
+        assert_eq!(
+            split_keybind("ctrl+==increase_font_size:1"),
+            Some(("ctrl+=", "increase_font_size:1"))
+        );
+        assert_eq!(
+            split_keybind("ctrl+a>==ignore"),
+            Some(("ctrl+a>=", "ignore"))
+        );
+        assert_eq!(
+            split_keybind("global:==ignore"),
+            Some(("global:=", "ignore"))
+        );
+    }
+
+    #[test]
+    fn keybind_can_bind_the_plus_key() {
+        assert_eq!(
+            split_keybind("ctrl++=increase_font_size:1"),
+            Some(("ctrl++", "increase_font_size:1"))
+        );
+        assert_eq!(
+            split_keybind("ctrl++=text:a=b"),
+            Some(("ctrl++", "text:a=b"))
+        );
+        assert_eq!(split_keybind("+=ignore"), Some(("+", "ignore")));
+    }
+
+    #[test]
+    fn keybind_without_action_does_not_split() {
+        assert_eq!(split_keybind("ctrl+a"), None);
+        assert_eq!(split_keybind("ctrl+="), None);
+    }
+
+    #[test]
+    fn binary_content_is_not_text() {
+        assert!(looks_like_text(
+            "font-size = 12\n\tkeybind = ctrl+a=ignore\r\n"
+        ));
+        assert!(looks_like_text(""));
+        assert!(!looks_like_text("font-size = 12\0"));
+        assert!(!looks_like_text("\u{1}\u{2}\u{3}ab"));
+        assert!(!looks_like_text(&String::from_utf8_lossy(&[
+            0xff, 0xfe, 0xfd, b'a'
+        ])));
+    }
+
+    #[test]
+    fn one_pair_of_double_quotes_is_removed() {
+        assert_eq!(unquote("\"/usr/bin/env fish\""), (1, "/usr/bin/env fish"));
+        assert_eq!(unquote("\"\"a\"\""), (1, "\"a\""));
+        assert_eq!(unquote("'single'"), (0, "'single'"));
+        assert_eq!(unquote("\"unterminated"), (0, "\"unterminated"));
+        assert_eq!(unquote("\""), (0, "\""));
+    }
+
+    #[test]
+    fn lines_are_classified_by_kind() {
+        let document = ParsedDocument::new("  \n# comment\n  font-size = 14 \nfont-fam\n");
+        let kinds: Vec<&LineKind> = document.lines().iter().map(|line| &line.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                &LineKind::Blank,
+                &LineKind::Comment,
+                &LineKind::Assignment {
+                    key: 2..11,
+                    eq: 12,
+                    value: 14..16,
+                },
+                &LineKind::Other,
+            ]
+        );
+    }
+
+    /// Each line's text and kind, for comparing documents.
+    fn parsed(document: &ParsedDocument) -> Vec<(String, LineKind)> {
+        document
+            .lines()
+            .iter()
+            .map(|line| (line.text.clone(), line.kind.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn update_matches_a_fresh_parse() {
+        let original = "a = 1\nb = 2\n# c\nd = 4\n";
+        for edited in [
+            // Inserted at the start, middle and end
+            "new = 0\na = 1\nb = 2\n# c\nd = 4\n",
+            "a = 1\nb = 2\nnew = 0\n# c\nd = 4\n",
+            "a = 1\nb = 2\n# c\nd = 4\nnew = 0\n",
+            // Deleted from the start, middle and end
+            "b = 2\n# c\nd = 4\n",
+            "a = 1\n# c\nd = 4\n",
+            "a = 1\nb = 2\n# c\n",
+            // Edited at the start, middle and end
+            "a = 10\nb = 2\n# c\nd = 4\n",
+            "a = 1\nb\n# c\nd = 4\n",
+            "a = 1\nb = 2\n# c\nd = \n",
+            // Repeated lines either side of the edit
+            "a = 1\na = 1\nb = 2\n# c\nd = 4\n",
+            "",
+        ] {
+            let mut document = ParsedDocument::new(original);
+            document.update(edited);
+            assert_eq!(document.text(), edited);
+            assert_eq!(
+                parsed(&document),
+                parsed(&ParsedDocument::new(edited)),
+                "{:?}",
+                edited
+            );
+        }
+    }
+}
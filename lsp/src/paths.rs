@@ -0,0 +1,138 @@
+use std::path::{Path, PathBuf};
+use tower_lsp::lsp_types::Url;
+
+/// Expands a leading `~` and `$VAR`/`${VAR}` references from the process
+/// environment. Variables that aren't set are left exactly as written.
+pub fn expand(value: &str) -> String {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+
+    if rest == "~" || rest.starts_with("~/") {
+        if let Some(home) = std::env::var_os("HOME") {
+            expanded.push_str(&home.to_string_lossy());
+            rest = &rest[1..];
+        }
+    }
+
+    while let Some(i) = rest.find('$') {
+        expanded.push_str(&rest[..i]);
+        let after = &rest[i + 1..];
+        let (name, consumed) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            },
+            None => {
+                let end = after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                (&after[..end], end)
+            }
+        };
+
+        match std::env::var(name) {
+            Ok(val) if !name.is_empty() => expanded.push_str(&val),
+            _ => expanded.push_str(&rest[i..i + 1 + consumed]),
+        }
+        rest = &after[consumed..];
+    }
+    expanded.push_str(rest);
+
+    expanded
+}
+
+/// Expands `value` and resolves it against `base_dir` when relative. Returns
+/// `None` for a relative path with no directory to resolve it against.
+pub fn resolve(value: &str, base_dir: Option<&Path>) -> Option<PathBuf> {
+    let path = PathBuf::from(expand(value));
+    if path.is_absolute() {
+        Some(path)
+    } else {
+        base_dir.map(|dir| dir.join(path))
+    }
+}
+
+/// The directory relative paths in a document are resolved against: the
+/// document's own directory, or the Ghostty config directory for a document
+/// that isn't saved to disk.
+pub fn document_dir(uri: &Url) -> Option<PathBuf> {
+    match uri.to_file_path() {
+        Ok(path) => path.parent().map(Path::to_path_buf),
+        Err(_) => config_dir(),
+    }
+}
+
+/// Ghostty's config directory: `$GHOSTTY_CONFIG_DIR`, then
+/// `$XDG_CONFIG_HOME/ghostty`, then `~/.config/ghostty`.
+pub fn config_dir() -> Option<PathBuf> {
+    let var = |name| std::env::var_os(name).filter(|v| !v.is_empty());
+    if let Some(dir) = var("GHOSTTY_CONFIG_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    if let Some(xdg) = var("XDG_CONFIG_HOME") {
+        return Some(PathBuf::from(xdg).join("ghostty"));
+    }
+    var("HOME").map(|home| PathBuf::from(home).join(".config").join("ghostty"))
+}
+
+/// Names of the custom themes in the config directory's `themes` folder.
+pub fn user_themes() -> Vec<String> {
+    let Some(Ok(entries)) = config_dir().map(|dir| std::fs::read_dir(dir.join("themes"))) else {
+        return vec![];
+    };
+    let mut themes: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| !name.starts_with('.'))
+        .collect();
+    themes.sort();
+    themes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn home_is_expanded() {
+        let home = std::env::var("HOME").expect("HOME is set");
+        let expected = format!("{}/x", home);
+        assert_eq!(expand("~/x"), expected);
+        assert_eq!(expand("$HOME/x"), expected);
+        assert_eq!(expand("${HOME}/x"), expected);
+        assert_eq!(expand("~"), home);
+        // Only a leading `~` is the home directory
+        assert_eq!(expand("a/~/x"), "a/~/x");
+    }
+
+    #[test]
+    fn unset_variables_are_left_as_written() {
+        let value = "$GHOSTTY_LSP_UNSET_VARIABLE/themes/${GHOSTTY_LSP_UNSET_VARIABLE}";
+        assert_eq!(expand(value), value);
+    }
+
+    #[test]
+    fn relative_paths_need_a_base_directory() {
+        assert_eq!(resolve("extra.conf", None), None);
+        assert_eq!(
+            resolve("extra.conf", Some(Path::new("/etc/ghostty"))),
+            Some(PathBuf::from("/etc/ghostty/extra.conf"))
+        );
+        assert_eq!(
+            resolve("/abs/extra.conf", Some(Path::new("/etc/ghostty"))),
+            Some(PathBuf::from("/abs/extra.conf"))
+        );
+    }
+
+    #[test]
+    fn saved_documents_resolve_from_their_own_directory() {
+        let uri = Url::parse("file:///home/me/.config/ghostty/config").unwrap();
+        assert_eq!(
+            document_dir(&uri),
+            Some(PathBuf::from("/home/me/.config/ghostty"))
+        );
+        let untitled = Url::parse("untitled:Untitled-1").unwrap();
+        assert_eq!(document_dir(&untitled), config_dir());
+    }
+}
@@ -0,0 +1,118 @@
+use tower_lsp::lsp_types::{Position, Range};
+
+use crate::parse::{split_keybind, strip_keybind_prefixes, utf16_col, ParsedDocument, ParsedLine};
+use crate::schema::GhosttySchema;
+
+/// A `keybind` line's action name, with its byte offset in the line.
+pub(crate) fn keybind_action<'a>(
+    schema: &GhosttySchema,
+    line: &'a ParsedLine,
+) -> Option<(usize, &'a str)> {
+    let assignment = line.assignment()?;
+    if assignment.key != "keybind" {
+        return None;
+    }
+    let keybind = schema.types.as_ref()?.keybind.as_ref()?;
+    let prefixes = keybind.prefixes.as_deref().unwrap_or_default();
+
+    let (_, rest) = strip_keybind_prefixes(assignment.value, prefixes);
+    let (_, action) = split_keybind(rest)?;
+    let name = action.split(':').next().unwrap_or_default().trim();
+    if name.is_empty() {
+        return None;
+    }
+    let start = assignment.value_start + assignment.value.len() - action.len();
+    Some((start, name))
+}
+
+/// The action under the cursor, when it's on the action of a `keybind` line.
+pub fn action_at(
+    schema: &GhosttySchema,
+    document: &ParsedDocument,
+    position: Position,
+) -> Option<String> {
+    action_span_at(schema, document, position).map(|(_, name)| name)
+}
+
+/// Like [`action_at`], with the range the action name covers.
+pub fn action_span_at(
+    schema: &GhosttySchema,
+    document: &ParsedDocument,
+    position: Position,
+) -> Option<(Range, String)> {
+    let line = document.line(position.line as usize)?;
+    let (start, name) = keybind_action(schema, line)?;
+    let start = utf16_col(&line.text, start);
+    let end = start + name.encode_utf16().count() as u32;
+    (start..=end).contains(&position.character).then(|| {
+        let range = Range::new(
+            Position::new(position.line, start),
+            Position::new(position.line, end),
+        );
+        (range, name.to_string())
+    })
+}
+
+/// The value of every `keybind` line bound to `action`.
+pub fn bindings_for_action(
+    schema: &GhosttySchema,
+    document: &ParsedDocument,
+    action: &str,
+) -> Vec<Range> {
+    document
+        .lines()
+        .iter()
+        .enumerate()
+        .filter_map(|(line_num, line)| {
+            let (_, name) = keybind_action(schema, line)?;
+            if name != action {
+                return None;
+            }
+            let assignment = line.assignment()?;
+            let line = line.text.as_str();
+            let end = assignment.value_start + assignment.value.len();
+            Some(Range::new(
+                Position::new(line_num as u32, utf16_col(line, assignment.value_start)),
+                Position::new(line_num as u32, utf16_col(line, end)),
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "keybind = ctrl+t=new_tab\n\
+                          keybind = global:super+t=new_tab\n\
+                          keybind = ctrl+1=goto_tab:1\n\
+                          font-size = 12\n";
+
+    #[test]
+    fn finds_the_action_under_the_cursor() {
+        let schema = GhosttySchema::load();
+        let document = ParsedDocument::new(CONFIG);
+        assert_eq!(
+            action_at(&schema, &document, Position::new(2, 20)).as_deref(),
+            Some("goto_tab")
+        );
+        // On the trigger rather than the action
+        assert_eq!(action_at(&schema, &document, Position::new(0, 12)), None);
+        assert_eq!(action_at(&schema, &document, Position::new(3, 2)), None);
+    }
+
+    #[test]
+    fn lists_every_binding_of_an_action() {
+        let schema = GhosttySchema::load();
+        let document = ParsedDocument::new(CONFIG);
+        let ranges = bindings_for_action(&schema, &document, "new_tab");
+        assert_eq!(
+            ranges,
+            [
+                Range::new(Position::new(0, 10), Position::new(0, 24)),
+                Range::new(Position::new(1, 10), Position::new(1, 32)),
+            ]
+        );
+        assert!(bindings_for_action(&schema, &document, "close_tab").is_empty());
+    }
+}
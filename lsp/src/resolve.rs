@@ -0,0 +1,210 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::parse::parse_assignment;
+use crate::paths;
+use crate::schema::GhosttySchema;
+
+const CONFIG_FILE_KEY: &str = "config-file";
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum ResolvedValue {
+    Single(String),
+    List(Vec<String>),
+}
+
+/// The effective configuration after overrides, repeatable keys and
+/// `config-file` includes have been applied in the order Ghostty applies them.
+#[derive(Debug, Default)]
+pub struct ResolvedConfig {
+    pub values: BTreeMap<String, ResolvedValue>,
+    pub errors: Vec<String>,
+}
+
+/// `config-file` includes are only read when `follow_includes` is set;
+/// otherwise just `text` is resolved.
+pub fn resolve_config(
+    schema: &GhosttySchema,
+    text: &str,
+    path: Option<&Path>,
+    follow_includes: bool,
+) -> ResolvedConfig {
+    let mut resolver = Resolver {
+        schema,
+        config: ResolvedConfig::default(),
+        visited: HashSet::new(),
+        follow_includes,
+    };
+    if let Some(path) = path.filter(|_| follow_includes) {
+        resolver.visited.insert(canonical(path));
+    }
+    // Without a file of its own, relative includes resolve from the config directory
+    let base_dir = match path {
+        Some(path) => path.parent().map(Path::to_path_buf),
+        None => paths::config_dir(),
+    };
+    resolver.load(text, base_dir.as_deref());
+    resolver.config
+}
+
+/// The files `text` includes directly through `config-file`, resolved
+/// against `base_dir`. Includes that can't be resolved are left out.
+pub fn include_paths(text: &str, base_dir: Option<&Path>) -> Vec<PathBuf> {
+    text.lines()
+        .filter_map(parse_assignment)
+        .map(|assignment| assignment.unquoted())
+        .filter(|assignment| assignment.key == CONFIG_FILE_KEY && !assignment.value.is_empty())
+        .filter_map(|assignment| paths::resolve(parse_include(assignment.value).1, base_dir))
+        .collect()
+}
+
+/// Splits an include into whether it's optional (`?path`) and its path.
+fn parse_include(include: &str) -> (bool, &str) {
+    let (optional, raw_path) = match include.strip_prefix('?') {
+        Some(rest) => (true, rest),
+        None => (false, include),
+    };
+    (optional, raw_path.trim_matches('"'))
+}
+
+struct Resolver<'a> {
+    schema: &'a GhosttySchema,
+    config: ResolvedConfig,
+    visited: HashSet<PathBuf>,
+    follow_includes: bool,
+}
+
+impl Resolver<'_> {
+    fn load(&mut self, text: &str, base_dir: Option<&Path>) {
+        // Ghostty loads `config-file` includes after the file that references them
+        let mut includes: Vec<String> = vec![];
+
+        for line in text.lines() {
+            // Values are recorded as Ghostty reads them, without surrounding quotes
+            let Some(assignment) = parse_assignment(line).map(|a| a.unquoted()) else {
+                continue;
+            };
+
+            if assignment.key == CONFIG_FILE_KEY && self.follow_includes {
+                if assignment.value.is_empty() {
+                    includes.clear();
+                } else {
+                    includes.push(assignment.value.to_string());
+                }
+                continue;
+            }
+
+            self.apply(assignment.key, assignment.value);
+        }
+
+        for include in includes {
+            self.load_include(&include, base_dir);
+        }
+    }
+
+    fn apply(&mut self, key: &str, value: &str) {
+        let repeatable = self
+            .schema
+            .options
+            .get(key)
+            .is_some_and(|opt| opt.repeatable);
+
+        // An empty value resets the option to its default
+        if value.is_empty() {
+            self.config.values.remove(key);
+            return;
+        }
+
+        if repeatable {
+            let entry = self
+                .config
+                .values
+                .entry(key.to_string())
+                .or_insert_with(|| ResolvedValue::List(vec![]));
+            if let ResolvedValue::List(values) = entry {
+                values.push(value.to_string());
+            }
+        } else {
+            self.config
+                .values
+                .insert(key.to_string(), ResolvedValue::Single(value.to_string()));
+        }
+    }
+
+    fn load_include(&mut self, include: &str, base_dir: Option<&Path>) {
+        let (optional, raw_path) = parse_include(include);
+
+        let Some(path) = paths::resolve(raw_path, base_dir) else {
+            self.config
+                .errors
+                .push(format!("Cannot resolve relative include `{}`", raw_path));
+            return;
+        };
+
+        if !self.visited.insert(canonical(&path)) {
+            self.config
+                .errors
+                .push(format!("Include cycle detected at `{}`", path.display()));
+            return;
+        }
+
+        match std::fs::read_to_string(&path) {
+            Ok(text) => self.load(&text, path.parent()),
+            Err(_) if optional => {}
+            Err(e) => {
+                self.config
+                    .errors
+                    .push(format!("Failed to read `{}`: {}", path.display(), e))
+            }
+        }
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(text: &str) -> ResolvedConfig {
+        resolve_config(&GhosttySchema::load(), text, None, false)
+    }
+
+    fn single<'a>(config: &'a ResolvedConfig, key: &str) -> Option<&'a str> {
+        match config.values.get(key)? {
+            ResolvedValue::Single(value) => Some(value),
+            ResolvedValue::List(_) => None,
+        }
+    }
+
+    #[test]
+    fn surrounding_double_quotes_are_removed() {
+        let config = resolve("command = \"/usr/bin/env fish\"\ntitle = 'my term'\n");
+        assert_eq!(single(&config, "command"), Some("/usr/bin/env fish"));
+        assert_eq!(single(&config, "title"), Some("'my term'"));
+    }
+
+    #[test]
+    fn empty_quotes_reset_the_option() {
+        let config = resolve("font-size = 14\nfont-size = \"\"\n");
+        assert_eq!(single(&config, "font-size"), None);
+    }
+
+    #[test]
+    fn include_paths_resolve_against_the_document() {
+        let text =
+            "config-file = extra.conf\nconfig-file = ?\"optional.conf\"\nconfig-file = \"\"\n";
+        assert_eq!(
+            include_paths(text, Some(Path::new("/etc/ghostty"))),
+            [
+                PathBuf::from("/etc/ghostty/extra.conf"),
+                PathBuf::from("/etc/ghostty/optional.conf"),
+            ]
+        );
+        assert!(include_paths(text, None).is_empty());
+    }
+}
@@ -0,0 +1,433 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Embedded schemas keyed by the Ghostty `major.minor` release they describe,
+/// oldest first. Add an entry when a release adds or removes options.
+const SCHEMAS: &[((u32, u32), &str)] = &[
+    ((1, 1), include_str!("../../schema/ghostty-1.1.schema.json")),
+    (
+        (1, 2),
+        include_str!("../../schema/ghostty-config.schema.json"),
+    ),
+];
+
+/// Option types the server knows how to complete and validate.
+const KNOWN_TYPES: &[&str] = &[
+    "boolean",
+    "color",
+    "command",
+    "duration",
+    "enum",
+    "flags",
+    "font-variation",
+    "keybind",
+    "keyvalue-list",
+    "number",
+    "path",
+    "percentage",
+    "regex",
+    "string",
+    "theme",
+];
+
+const KNOWN_PLATFORMS: &[&str] = &["linux", "macos"];
+
+#[derive(Debug, Deserialize)]
+pub struct GhosttySchema {
+    /// The schema file's own revision, separate from the Ghostty release.
+    pub version: Option<String>,
+    pub options: HashMap<String, ConfigOption>,
+    pub types: Option<TypeDefinitions>,
+    #[serde(rename = "repeatableKeys")]
+    pub repeatable_keys: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConfigOption {
+    #[serde(rename = "type")]
+    pub option_type: String,
+    pub description: String,
+    /// The value Ghostty uses when the option isn't set, where there's a
+    /// single one. Given for booleans so toggling knows which way is off.
+    pub default: Option<String>,
+    #[serde(default)]
+    pub repeatable: bool,
+    #[serde(default)]
+    pub deprecated: bool,
+    /// Options that may change or go away in a later release.
+    #[serde(default)]
+    pub experimental: bool,
+    /// A caveat worth calling out, such as needing a restart.
+    pub note: Option<String>,
+    /// Included in the starter config offered for empty files.
+    #[serde(default)]
+    pub important: bool,
+    #[serde(rename = "enum")]
+    pub enum_values: Option<Vec<String>>,
+    /// Feature names a `flags` option accepts in its comma-separated list.
+    pub flags: Option<Vec<String>>,
+    /// Whether each feature can also be written with a `no-` prefix to turn
+    /// it off.
+    #[serde(default)]
+    pub negatable: bool,
+    pub examples: Option<Vec<String>>,
+    /// Bounds for `number` options, inclusive.
+    pub minimum: Option<f64>,
+    pub maximum: Option<f64>,
+    /// `number` options that only take whole numbers.
+    #[serde(default)]
+    pub integer: bool,
+    /// What a `number` counts, such as `cells` or `pixels`.
+    pub unit: Option<String>,
+    pub platforms: Option<Vec<String>>,
+    /// Longest value, in characters, that's plausibly intended. Longer values
+    /// are usually paste accidents.
+    #[serde(rename = "maxLength")]
+    pub max_length: Option<usize>,
+    /// Another option's setting without which this one does nothing.
+    pub requires: Option<Requirement>,
+    /// Related options that are easily confused with this one.
+    #[serde(rename = "seeAlso")]
+    pub see_also: Option<Vec<String>>,
+}
+
+impl ConfigOption {
+    /// The option's types: usually one, or each member of a union such as
+    /// `boolean|number`, which accepts a value of any of them.
+    pub fn types(&self) -> impl Iterator<Item = &str> {
+        self.option_type.split('|')
+    }
+
+    pub fn has_type(&self, option_type: &str) -> bool {
+        self.types().any(|t| t == option_type)
+    }
+}
+
+/// A prerequisite on another option, met by the value the file sets it to.
+/// With neither `oneOf` nor `below`, any non-empty value meets it.
+#[derive(Debug, Deserialize)]
+pub struct Requirement {
+    pub key: String,
+    #[serde(rename = "oneOf")]
+    pub one_of: Option<Vec<String>>,
+    /// A number the value must be less than.
+    pub below: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TypeDefinitions {
+    pub keybind: Option<KeybindType>,
+    pub color: Option<ColorType>,
+    #[serde(rename = "fontVariation")]
+    pub font_variation: Option<FontVariationType>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct KeybindType {
+    pub prefixes: Option<Vec<String>>,
+    pub modifiers: Option<Vec<String>>,
+    /// Named keys a trigger can end in. Single characters are always accepted.
+    pub keys: Option<Vec<String>>,
+    pub actions: Option<Vec<String>>,
+    /// Actions that take an argument after a `:`. Actions not listed take none.
+    #[serde(rename = "actionArguments")]
+    pub action_arguments: Option<HashMap<String, ActionArgument>>,
+    /// One-line summaries shown next to bound actions.
+    #[serde(rename = "actionDescriptions")]
+    pub action_descriptions: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ActionArgument {
+    pub required: bool,
+    pub expects: String,
+    pub example: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ColorType {
+    #[serde(rename = "namedValues")]
+    pub named_values: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FontVariationType {
+    /// Registered axes offered as completions. Fonts may define others.
+    pub axes: Option<Vec<VariationAxis>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VariationAxis {
+    pub tag: String,
+    pub name: String,
+    pub example: String,
+}
+
+impl GhosttySchema {
+    /// Loads the newest embedded schema.
+    pub fn load() -> Self {
+        Self::for_version(None)
+    }
+
+    /// Loads the embedded schema closest to `version`, such as `1.1.3` or
+    /// `Ghostty 1.1.3`: the newest one not newer than it, or the oldest when it
+    /// predates them all. Unknown versions get the newest schema.
+    pub fn for_version(version: Option<&str>) -> Self {
+        let json = match version.and_then(parse_version) {
+            Some(wanted) => {
+                SCHEMAS
+                    .iter()
+                    .rev()
+                    .find(|(v, _)| *v <= wanted)
+                    .unwrap_or(&SCHEMAS[0])
+                    .1
+            }
+            None => SCHEMAS[SCHEMAS.len() - 1].1,
+        };
+        serde_json::from_str(json).expect("Failed to parse embedded schema")
+    }
+}
+
+const DOCS_URL: &str = "https://ghostty.org/docs/config/reference";
+
+/// The option's entry in Ghostty's configuration reference.
+pub fn docs_url(key: &str) -> String {
+    format!("{}#{}", DOCS_URL, key)
+}
+
+/// The newest Ghostty `major.minor` release an embedded schema describes.
+pub fn newest_release() -> (u32, u32) {
+    SCHEMAS[SCHEMAS.len() - 1].0
+}
+
+/// Reads `major.minor` from a version string, ignoring any leading words and
+/// the patch or build suffix.
+pub fn parse_version(text: &str) -> Option<(u32, u32)> {
+    text.split_whitespace().find_map(|word| {
+        let mut parts = word.trim_start_matches('v').split(['.', '-', '+']);
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next()?.parse().ok()?;
+        Some((major, minor))
+    })
+}
+
+/// Asks the installed `ghostty` for its version. `None` when it isn't on
+/// `PATH`, prints something unexpected or doesn't answer within `timeout`.
+pub async fn detect_ghostty_version(timeout: Duration) -> Option<String> {
+    let command = tokio::process::Command::new("ghostty")
+        .arg("--version")
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(timeout, command).await.ok()?.ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout.lines().find(|l| parse_version(l).is_some())?;
+    Some(line.trim().to_string())
+}
+
+/// Self-consistency check on the embedded schema, returning one message per problem.
+pub fn validate_schema(schema: &GhosttySchema) -> Vec<String> {
+    let mut problems = vec![];
+
+    let mut keys: Vec<&String> = schema.options.keys().collect();
+    keys.sort();
+
+    for key in keys {
+        let opt = &schema.options[key];
+
+        for option_type in opt.types() {
+            if !KNOWN_TYPES.contains(&option_type) {
+                problems.push(format!("`{}` has unknown type `{}`", key, option_type));
+            }
+        }
+        if opt.description.trim().is_empty() {
+            problems.push(format!("`{}` has an empty description", key));
+        }
+        if opt.has_type("enum") && opt.enum_values.as_ref().is_none_or(|v| v.is_empty()) {
+            problems.push(format!("`{}` is an enum without enum values", key));
+        }
+        if !opt.has_type("enum") && opt.enum_values.is_some() {
+            problems.push(format!(
+                "`{}` has enum values but type `{}`",
+                key, opt.option_type
+            ));
+        }
+        if opt.has_type("flags") && opt.flags.as_ref().is_none_or(|v| v.is_empty()) {
+            problems.push(format!("`{}` is a flags option without flags", key));
+        }
+        if opt.negatable && opt.flags.is_none() {
+            problems.push(format!("`{}` is negatable but has no flags", key));
+        }
+        if !opt.has_type("flags") && opt.flags.is_some() {
+            problems.push(format!(
+                "`{}` has flags but type `{}`",
+                key, opt.option_type
+            ));
+        }
+        let has_number_metadata =
+            opt.minimum.is_some() || opt.maximum.is_some() || opt.integer || opt.unit.is_some();
+        if !opt.has_type("number") && has_number_metadata {
+            problems.push(format!(
+                "`{}` has number constraints but type `{}`",
+                key, opt.option_type
+            ));
+        }
+        if opt.option_type == "boolean"
+            && opt
+                .default
+                .as_deref()
+                .is_some_and(|d| !matches!(d, "true" | "false"))
+        {
+            problems.push(format!("`{}` has a non-boolean default", key));
+        }
+        if opt.note.as_ref().is_some_and(|n| n.trim().is_empty()) {
+            problems.push(format!("`{}` has an empty note", key));
+        }
+        if opt.max_length == Some(0) {
+            problems.push(format!("`{}` has a maximum length of 0", key));
+        }
+        if let Some(requires) = &opt.requires {
+            if !schema.options.contains_key(&requires.key) {
+                problems.push(format!(
+                    "`{}` requires unknown option `{}`",
+                    key, requires.key
+                ));
+            }
+        }
+        for related in opt.see_also.iter().flatten() {
+            if !schema.options.contains_key(related) {
+                problems.push(format!("`{}` refers to unknown option `{}`", key, related));
+            }
+        }
+        for platform in opt.platforms.iter().flatten() {
+            if !KNOWN_PLATFORMS.contains(&platform.as_str()) {
+                problems.push(format!("`{}` has unknown platform `{}`", key, platform));
+            }
+        }
+    }
+
+    if let Some(repeatable_keys) = &schema.repeatable_keys {
+        for key in repeatable_keys {
+            match schema.options.get(key) {
+                None => problems.push(format!("repeatable key `{}` is not a known option", key)),
+                Some(opt) if !opt.repeatable => problems.push(format!(
+                    "`{}` is listed as repeatable but not marked so",
+                    key
+                )),
+                _ => {}
+            }
+        }
+    }
+
+    if let Some(font_variation) = schema
+        .types
+        .as_ref()
+        .and_then(|t| t.font_variation.as_ref())
+    {
+        for axis in font_variation.axes.iter().flatten() {
+            if axis.tag.len() != 4 {
+                problems.push(format!("axis tag `{}` isn't four characters", axis.tag));
+            }
+        }
+    }
+
+    if let Some(keybind) = schema.types.as_ref().and_then(|t| t.keybind.as_ref()) {
+        let actions = keybind.actions.as_deref().unwrap_or_default();
+        let mut described: Vec<&String> = keybind
+            .action_descriptions
+            .iter()
+            .flatten()
+            .map(|(a, _)| a)
+            .collect();
+        described.sort();
+        for action in described {
+            if !actions.contains(action) {
+                problems.push(format!("description for unknown action `{}`", action));
+            }
+        }
+
+        let mut with_arguments: Vec<&String> = keybind
+            .action_arguments
+            .iter()
+            .flatten()
+            .map(|(a, _)| a)
+            .collect();
+        with_arguments.sort();
+        for action in with_arguments {
+            if !actions.contains(action) {
+                problems.push(format!("argument for unknown action `{}`", action));
+            }
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_schema_is_consistent() {
+        assert_eq!(
+            validate_schema(&GhosttySchema::load()),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn every_embedded_schema_is_consistent() {
+        for ((major, minor), json) in SCHEMAS {
+            let schema: GhosttySchema = serde_json::from_str(json).unwrap();
+            assert_eq!(
+                validate_schema(&schema),
+                Vec::<String>::new(),
+                "schema for Ghostty {}.{}",
+                major,
+                minor
+            );
+        }
+    }
+
+    #[test]
+    fn reads_major_and_minor_from_version_output() {
+        assert_eq!(parse_version("Ghostty 1.1.3"), Some((1, 1)));
+        assert_eq!(parse_version("ghostty v1.2.0-dev+abc123"), Some((1, 2)));
+        assert_eq!(parse_version("1.0"), Some((1, 0)));
+        assert_eq!(parse_version("Ghostty tip"), None);
+        assert_eq!(parse_version("1"), None);
+    }
+
+    #[test]
+    fn every_version_gets_a_schema() {
+        let newest = GhosttySchema::for_version(None);
+        let (major, minor) = newest_release();
+        let same = GhosttySchema::for_version(Some(&format!("{}.{}.0", major, minor)));
+        assert_eq!(newest.options.len(), same.options.len());
+
+        // Older and unknown releases fall back rather than fail
+        assert!(!GhosttySchema::for_version(Some("0.1")).options.is_empty());
+        assert_eq!(
+            GhosttySchema::for_version(Some("99.0")).options.len(),
+            newest.options.len()
+        );
+        assert_eq!(
+            GhosttySchema::for_version(Some("nightly")).options.len(),
+            newest.options.len()
+        );
+    }
+
+    #[test]
+    fn older_release_lacks_newer_options() {
+        let older = GhosttySchema::for_version(Some("Ghostty 1.1.3"));
+        assert!(older.options.contains_key("font-size"));
+        assert!(!older.options.contains_key("background-image"));
+        assert!(GhosttySchema::load()
+            .options
+            .contains_key("background-image"));
+
+        let diagnostics = crate::lint(&older, "background-image = ~/bg.png\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("Unknown configuration key"));
+    }
+}
@@ -0,0 +1,92 @@
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// What [`Stats`] times. Every kind is listed by `ghostty.stats`, with a count
+/// of 0 until it has been recorded.
+pub const KINDS: &[&str] = &[
+    "completion",
+    "diagnostics",
+    "formatting",
+    "rangeFormatting",
+    "hover",
+    "inlayHint",
+    "references",
+    "codeAction",
+];
+
+/// In-memory request counters for diagnosing slowness. Nothing is persisted or
+/// sent anywhere; the numbers are only returned by the `ghostty.stats` command.
+#[derive(Default)]
+pub struct Stats {
+    entries: Mutex<BTreeMap<&'static str, Entry>>,
+}
+
+#[derive(Default)]
+struct Entry {
+    count: u64,
+    total: Duration,
+    max: Duration,
+}
+
+impl Stats {
+    pub fn record(&self, kind: &'static str, elapsed: Duration) {
+        debug_assert!(KINDS.contains(&kind), "unlisted stats kind {}", kind);
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.entry(kind).or_default();
+        entry.count += 1;
+        entry.total += elapsed;
+        entry.max = entry.max.max(elapsed);
+    }
+
+    pub fn to_json(&self) -> Value {
+        let entries = self.entries.lock().unwrap();
+        let unrecorded = Entry::default();
+        let map: Map<String, Value> = KINDS
+            .iter()
+            .map(|kind| {
+                let entry = entries.get(kind).unwrap_or(&unrecorded);
+                let average = if entry.count == 0 {
+                    0.0
+                } else {
+                    entry.total.as_secs_f64() * 1000.0 / entry.count as f64
+                };
+                (
+                    kind.to_string(),
+                    json!({
+                        "count": entry.count,
+                        "averageMs": average,
+                        "maxMs": entry.max.as_secs_f64() * 1000.0,
+                    }),
+                )
+            })
+            .collect();
+        Value::Object(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_every_kind_before_anything_is_recorded() {
+        let json = Stats::default().to_json();
+        for kind in KINDS {
+            assert_eq!(json[kind]["count"], 0, "{}", kind);
+            assert_eq!(json[kind]["averageMs"], 0.0, "{}", kind);
+        }
+    }
+
+    #[test]
+    fn averages_and_maxima() {
+        let stats = Stats::default();
+        stats.record("hover", Duration::from_millis(2));
+        stats.record("hover", Duration::from_millis(4));
+        let json = stats.to_json();
+        assert_eq!(json["hover"]["count"], 2);
+        assert_eq!(json["hover"]["averageMs"], 3.0);
+        assert_eq!(json["hover"]["maxMs"], 4.0);
+    }
+}
@@ -0,0 +1,58 @@
+use crate::schema::GhosttySchema;
+
+/// Sections of the starter config, each claiming the important options whose
+/// key starts with one of its prefixes. Anything unclaimed goes under "Other".
+const SECTIONS: &[(&str, &[&str])] = &[
+    ("Font", &["font-"]),
+    ("Appearance", &["theme", "background-", "window-"]),
+    ("Cursor and mouse", &["cursor-", "mouse-"]),
+    ("Behaviour", &["copy-", "confirm-", "shell-", "macos-"]),
+    ("Keybindings", &["keybind"]),
+];
+
+/// A commented starter config covering the options the schema marks as
+/// `important`. Every assignment is commented out, so inserting it changes
+/// nothing until a line is uncommented.
+pub fn starter_config(schema: &GhosttySchema) -> String {
+    let mut keys: Vec<&String> = schema
+        .options
+        .iter()
+        .filter(|(_, opt)| opt.important)
+        .map(|(key, _)| key)
+        .collect();
+    keys.sort();
+
+    let section_of = |key: &str| {
+        SECTIONS
+            .iter()
+            .position(|(_, prefixes)| prefixes.iter().any(|p| key.starts_with(p)))
+            .unwrap_or(SECTIONS.len())
+    };
+
+    let mut text = String::from(
+        "# Ghostty configuration\n# Uncomment a line to change that option from its default.\n",
+    );
+    for section in 0..=SECTIONS.len() {
+        let in_section: Vec<&&String> = keys.iter().filter(|k| section_of(k) == section).collect();
+        if in_section.is_empty() {
+            continue;
+        }
+        let title = SECTIONS.get(section).map_or("Other", |(title, _)| title);
+        text.push_str(&format!("\n# --- {} ---\n", title));
+
+        for key in in_section {
+            let opt = &schema.options[*key];
+            let example = opt
+                .examples
+                .as_ref()
+                .and_then(|e| e.first())
+                .map(String::as_str)
+                .unwrap_or_default();
+            text.push_str(&format!(
+                "\n# {}\n# {} = {}\n",
+                opt.description, key, example
+            ));
+        }
+    }
+    text
+}
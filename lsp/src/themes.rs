@@ -0,0 +1,178 @@
+use std::path::Path;
+
+use crate::parse::parse_assignment;
+use crate::paths;
+
+/// Background, foreground, then palette entries 1 to 6 (red, green, yellow,
+/// blue, magenta, cyan).
+type Palette = [&'static str; 8];
+
+const PALETTE_LABELS: [&str; 8] = [
+    "background",
+    "foreground",
+    "palette 1",
+    "palette 2",
+    "palette 3",
+    "palette 4",
+    "palette 5",
+    "palette 6",
+];
+
+/// Themes bundled with Ghostty that completion offers, with a few colours
+/// each for previewing.
+pub const BUILTIN: &[(&str, Palette)] = &[
+    (
+        "Catppuccin Mocha",
+        [
+            "#1e1e2e", "#cdd6f4", "#f38ba8", "#a6e3a1", "#f9e2af", "#89b4fa", "#f5c2e7", "#94e2d5",
+        ],
+    ),
+    (
+        "Catppuccin Macchiato",
+        [
+            "#24273a", "#cad3f5", "#ed8796", "#a6da95", "#eed49f", "#8aadf4", "#f5bde6", "#8bd5ca",
+        ],
+    ),
+    (
+        "Catppuccin Frappe",
+        [
+            "#303446", "#c6d0f5", "#e78284", "#a6d189", "#e5c890", "#8caaee", "#f4b8e4", "#81c8be",
+        ],
+    ),
+    (
+        "Catppuccin Latte",
+        [
+            "#eff1f5", "#4c4f69", "#d20f39", "#40a02b", "#df8e1d", "#1e66f5", "#ea76cb", "#179299",
+        ],
+    ),
+    (
+        "Dracula",
+        [
+            "#282a36", "#f8f8f2", "#ff5555", "#50fa7b", "#f1fa8c", "#bd93f9", "#ff79c6", "#8be9fd",
+        ],
+    ),
+    (
+        "Gruvbox Dark",
+        [
+            "#282828", "#ebdbb2", "#cc241d", "#98971a", "#d79921", "#458588", "#b16286", "#689d6a",
+        ],
+    ),
+    (
+        "Gruvbox Light",
+        [
+            "#fbf1c7", "#3c3836", "#cc241d", "#98971a", "#d79921", "#458588", "#b16286", "#689d6a",
+        ],
+    ),
+    (
+        "Nord",
+        [
+            "#2e3440", "#d8dee9", "#bf616a", "#a3be8c", "#ebcb8b", "#81a1c1", "#b48ead", "#88c0d0",
+        ],
+    ),
+    (
+        "One Dark",
+        [
+            "#282c34", "#abb2bf", "#e06c75", "#98c379", "#e5c07b", "#61afef", "#c678dd", "#56b6c2",
+        ],
+    ),
+    (
+        "Solarized Dark",
+        [
+            "#002b36", "#839496", "#dc322f", "#859900", "#b58900", "#268bd2", "#d33682", "#2aa198",
+        ],
+    ),
+    (
+        "Solarized Light",
+        [
+            "#fdf6e3", "#657b83", "#dc322f", "#859900", "#b58900", "#268bd2", "#d33682", "#2aa198",
+        ],
+    ),
+    (
+        "Tokyo Night",
+        [
+            "#1a1b26", "#c0caf5", "#f7768e", "#9ece6a", "#e0af68", "#7aa2f7", "#bb9af7", "#7dcfff",
+        ],
+    ),
+    (
+        "Tokyo Night Storm",
+        [
+            "#24283b", "#c0caf5", "#f7768e", "#9ece6a", "#e0af68", "#7aa2f7", "#bb9af7", "#7dcfff",
+        ],
+    ),
+    (
+        "Tomorrow Night",
+        [
+            "#1d1f21", "#c5c8c6", "#cc6666", "#b5bd68", "#f0c674", "#81a2be", "#b294bb", "#8abeb7",
+        ],
+    ),
+];
+
+/// Markdown previewing the theme called `name`: a bundled one, or with
+/// `user_themes`, one in the config directory's `themes` folder.
+pub fn preview(name: &str, user_themes: bool) -> Option<String> {
+    if let Some((_, palette)) = BUILTIN.iter().find(|(builtin, _)| *builtin == name) {
+        return Some(builtin_preview(palette));
+    }
+    // Resolved items come back from the client, so keep the name inside `themes`
+    if !user_themes || name.starts_with('.') || name.contains(['/', '\\']) {
+        return None;
+    }
+    file_preview(&paths::config_dir()?.join("themes").join(name))
+}
+
+/// Markdown previewing a bundled theme's colours.
+fn builtin_preview(palette: &Palette) -> String {
+    let colours: Vec<(&str, &str)> = PALETTE_LABELS
+        .iter()
+        .copied()
+        .zip(palette.iter().copied())
+        .collect();
+    colour_table(&colours)
+}
+
+/// Markdown previewing the background, foreground and first palette entries
+/// a theme file sets. `None` when it sets none of them or can't be read.
+fn file_preview(path: &Path) -> Option<String> {
+    let text = std::fs::read_to_string(path).ok()?;
+    let mut colours: Vec<(&str, &str)> = vec![];
+    let mut palette: Vec<(usize, &str)> = vec![];
+
+    for line in text.lines() {
+        let Some(assignment) = parse_assignment(line) else {
+            continue;
+        };
+        match assignment.key {
+            "background" => colours.push(("background", assignment.value)),
+            "foreground" => colours.push(("foreground", assignment.value)),
+            "palette" => {
+                let Some((index, colour)) = assignment.value.split_once('=') else {
+                    continue;
+                };
+                if let Ok(index @ 1..=6) = index.trim().parse::<usize>() {
+                    palette.push((index, colour.trim()));
+                }
+            }
+            _ => {}
+        }
+    }
+    if colours.is_empty() && palette.is_empty() {
+        return None;
+    }
+
+    palette.sort_by_key(|(index, _)| *index);
+    colours.sort_by_key(|(label, _)| *label != "background");
+    colours.extend(
+        palette
+            .iter()
+            .map(|(index, colour)| (PALETTE_LABELS[index + 1], *colour)),
+    );
+    Some(colour_table(&colours))
+}
+
+fn colour_table(colours: &[(&str, &str)]) -> String {
+    let mut md = String::from("| | |\n|---|---|\n");
+    for (label, colour) in colours {
+        md.push_str(&format!("| {} | `{}` |\n", label, colour));
+    }
+    md
+}
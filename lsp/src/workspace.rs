@@ -0,0 +1,181 @@
+use std::path::{Path, PathBuf};
+
+const IGNORE_FILE: &str = ".ghosttyignore";
+
+/// Directories that never contain configs worth scanning.
+const SKIPPED_DIRS: &[&str] = &[".git", "node_modules", "target"];
+
+const MAX_DEPTH: usize = 8;
+
+/// Finds Ghostty config files under `root`, using the same naming rules as the
+/// extension's `path_suffixes`. Paths matching `exclude_globs` or a pattern in
+/// the root's `.ghosttyignore` are skipped.
+pub fn discover_config_files(root: &Path, exclude_globs: &[String]) -> Vec<PathBuf> {
+    let mut patterns: Vec<String> = exclude_globs.to_vec();
+    if let Ok(ignore) = std::fs::read_to_string(root.join(IGNORE_FILE)) {
+        patterns.extend(
+            ignore
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                .map(String::from),
+        );
+    }
+
+    let mut found = vec![];
+    walk(root, root, &patterns, 0, &mut found);
+    found.sort();
+    found
+}
+
+fn walk(root: &Path, dir: &Path, patterns: &[String], depth: usize, found: &mut Vec<PathBuf>) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(relative) = path.strip_prefix(root) else {
+            continue;
+        };
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        if patterns.iter().any(|p| is_excluded(p, &relative)) {
+            continue;
+        }
+
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            let name = entry.file_name();
+            if !SKIPPED_DIRS.contains(&name.to_string_lossy().as_ref()) {
+                walk(root, &path, patterns, depth + 1, found);
+            }
+        } else if file_type.is_file() && is_config_file(&path) {
+            found.push(path);
+        }
+    }
+}
+
+/// Whether `path` is named like a Ghostty config.
+pub fn is_config_file(path: &Path) -> bool {
+    if path.extension().is_some_and(|ext| ext == "ghostty") {
+        return true;
+    }
+    let parent = path
+        .parent()
+        .and_then(Path::file_name)
+        .map(|name| name.to_string_lossy());
+    path.file_name().is_some_and(|name| name == "config")
+        && matches!(parent.as_deref(), Some("ghostty" | "com.mitchellh.ghostty"))
+}
+
+/// gitignore-flavoured matching: a pattern without `/` matches a path component
+/// at any depth, a trailing `/` matches a directory and everything under it.
+fn is_excluded(pattern: &str, relative: &str) -> bool {
+    let pattern = pattern.trim_start_matches("./").trim_start_matches('/');
+    if let Some(dir) = pattern.strip_suffix('/') {
+        return is_excluded(dir, relative) || is_excluded(&format!("{}/**", dir), relative);
+    }
+    if pattern.contains('/') {
+        glob_match(pattern, relative)
+    } else {
+        relative
+            .split('/')
+            .any(|component| glob_match(pattern, component))
+    }
+}
+
+/// Minimal glob matcher supporting `*` (within a path component), `**` (across
+/// components) and `?`.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    matches_from(&p, &t)
+}
+
+fn matches_from(p: &[char], t: &[char]) -> bool {
+    match p.first() {
+        None => t.is_empty(),
+        Some('*') if p.get(1) == Some(&'*') => match p[2..].strip_prefix(&['/']) {
+            // `**/` only resumes matching at the start of a path component
+            Some(rest) => (0..=t.len())
+                .filter(|&i| i == 0 || t[i - 1] == '/')
+                .any(|i| matches_from(rest, &t[i..])),
+            None => (0..=t.len()).any(|i| matches_from(&p[2..], &t[i..])),
+        },
+        Some('*') => (0..=t.len())
+            .take_while(|&i| i == 0 || t[i - 1] != '/')
+            .any(|i| matches_from(&p[1..], &t[i..])),
+        Some('?') => !t.is_empty() && t[0] != '/' && matches_from(&p[1..], &t[1..]),
+        Some(c) => t.first() == Some(c) && matches_from(&p[1..], &t[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pattern_without_slash_matches_any_component() {
+        assert!(is_excluded("vendor", "vendor/ghostty/config"));
+        assert!(is_excluded("vendor", "a/b/vendor/config.ghostty"));
+        assert!(is_excluded("*.ghostty", "themes/dark.ghostty"));
+        assert!(is_excluded("backup-?", "backup-1/ghostty/config"));
+        assert!(!is_excluded("vendor", "vendored/config.ghostty"));
+        assert!(!is_excluded("backup-?", "backup-10/ghostty/config"));
+    }
+
+    #[test]
+    fn double_star_matches_at_any_depth() {
+        assert!(is_excluded("**/old/*.ghostty", "old/a.ghostty"));
+        assert!(is_excluded("**/old/*.ghostty", "x/y/old/a.ghostty"));
+        assert!(!is_excluded("**/old/*.ghostty", "x/bold/a.ghostty"));
+        assert!(!is_excluded("**/old/*.ghostty", "old/sub/a.ghostty"));
+        assert!(is_excluded("docs/**", "docs/examples/ghostty/config"));
+    }
+
+    #[test]
+    fn trailing_slash_matches_a_directory_and_its_contents() {
+        assert!(is_excluded("examples/", "examples"));
+        assert!(is_excluded("examples/", "examples/ghostty/config"));
+        assert!(is_excluded(
+            "./nested/examples/",
+            "nested/examples/a.ghostty"
+        ));
+        assert!(!is_excluded("nested/examples/", "examples/a.ghostty"));
+    }
+
+    #[test]
+    fn ghosttyignore_patterns_are_skipped() {
+        let root =
+            std::env::temp_dir().join(format!("ghostty-lsp-workspace-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for dir in ["ghostty", "examples/ghostty", "themes"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in [
+            "ghostty/config",
+            "examples/ghostty/config",
+            "themes/dark.ghostty",
+            "themes/light.ghostty",
+            "notes.txt",
+        ] {
+            std::fs::write(root.join(file), "").unwrap();
+        }
+        std::fs::write(root.join(IGNORE_FILE), "# samples\nexamples/\n\n").unwrap();
+
+        let found = discover_config_files(&root, &["light.*".to_string()]);
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(
+            found,
+            [
+                root.join("ghostty/config"),
+                root.join("themes/dark.ghostty")
+            ]
+        );
+    }
+}
@@ -0,0 +1,472 @@
+//! Drives the `ghostty-lsp` binary over stdio, for behaviour that lives in the
+//! server rather than the library.
+
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+/// How long to wait for any one message before failing the test.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+const URI: &str = "file:///tmp/ghostty-lsp-tests/config";
+const OTHER_URI: &str = "file:///tmp/ghostty-lsp-tests/other";
+
+struct Server {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    messages: Receiver<Value>,
+    next_id: u64,
+}
+
+impl Server {
+    /// Starts the server and completes the `initialize` handshake, passing
+    /// `options` as the initialization options.
+    fn start(options: Value) -> Self {
+        Self::start_with_env(options, &[])
+    }
+
+    /// Like [`Server::start`], with extra environment variables.
+    fn start_with_env(options: Value, env: &[(&str, &Path)]) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_ghostty-lsp"))
+            .envs(env.iter().copied())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("failed to start ghostty-lsp");
+        let stdin = child.stdin.take();
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+        let (tx, messages) = mpsc::channel();
+        std::thread::spawn(move || {
+            while let Some(message) = read_message(&mut stdout) {
+                if tx.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut server = Self {
+            child,
+            stdin,
+            messages,
+            next_id: 0,
+        };
+        server.request(
+            "initialize",
+            json!({ "capabilities": {}, "initializationOptions": options }),
+        );
+        server.notify("initialized", json!({}));
+        server
+    }
+
+    fn send(&mut self, message: Value) {
+        let body = message.to_string();
+        let stdin = self.stdin.as_mut().expect("stdin already closed");
+        write!(stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        stdin.flush().unwrap();
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    /// Sends a request and returns its result, answering whatever the
+    /// server asks of the client in the meantime.
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        let response =
+            self.wait_for(|message| message["id"] == id && message.get("method").is_none());
+        assert!(
+            response.get("error").is_none(),
+            "{} failed: {}",
+            method,
+            response
+        );
+        response["result"].clone()
+    }
+
+    /// The next message from the server matching `predicate`.
+    fn wait_for(&mut self, predicate: impl Fn(&Value) -> bool) -> Value {
+        loop {
+            let message = self
+                .messages
+                .recv_timeout(TIMEOUT)
+                .expect("timed out waiting for the server");
+            if message.get("method").is_some() && message.get("id").is_some() {
+                let id = message["id"].clone();
+                self.send(json!({ "jsonrpc": "2.0", "id": id, "result": null }));
+            }
+            if predicate(&message) {
+                return message;
+            }
+        }
+    }
+
+    /// The next diagnostics published for `uri`.
+    fn diagnostics(&mut self, uri: &str) -> Vec<Value> {
+        let message = self.wait_for(|message| {
+            message["method"] == "textDocument/publishDiagnostics"
+                && message["params"]["uri"] == uri
+        });
+        message["params"]["diagnostics"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn open(&mut self, uri: &str, text: &str) {
+        self.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": { "uri": uri, "languageId": "ghostty", "version": 1, "text": text }
+            }),
+        );
+    }
+
+    fn change(&mut self, uri: &str, version: i32, text: &str) {
+        self.notify(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": uri, "version": version },
+                "contentChanges": [{ "text": text }]
+            }),
+        );
+    }
+
+    fn complete(&mut self, uri: &str, line: u32, character: u32) -> Value {
+        self.request(
+            "textDocument/completion",
+            json!({
+                "textDocument": { "uri": uri },
+                "position": { "line": line, "character": character }
+            }),
+        )
+    }
+
+    /// Sends `exit` and returns the status the server leaves with.
+    fn exit(&mut self) -> Option<i32> {
+        self.notify("exit", json!(null));
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            if let Some(status) = self.child.try_wait().unwrap() {
+                return status.code();
+            }
+            assert!(Instant::now() < deadline, "timed out waiting for exit");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn read_message(reader: &mut impl BufRead) -> Option<Value> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).ok()? == 0 {
+            return None;
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length: ") {
+            length = value.parse().ok();
+        }
+    }
+    let mut body = vec![0; length?];
+    reader.read_exact(&mut body).ok()?;
+    serde_json::from_slice(&body).ok()
+}
+
+/// A fresh directory for one test's files.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("ghostty-lsp-test-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn labels(completions: &Value) -> Vec<String> {
+    let items = completions.get("items").unwrap_or(completions);
+    items
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn completion_list_is_capped_and_marked_incomplete() {
+    let mut server = Server::start(json!({ "maxCompletions": 5 }));
+    server.open(URI, "");
+    let completions = server.complete(URI, 0, 0);
+    assert_eq!(completions["isIncomplete"], true);
+    assert_eq!(labels(&completions).len(), 5);
+}
+
+#[test]
+fn short_completion_list_is_complete() {
+    let mut server = Server::start(json!({ "maxCompletions": 5 }));
+    server.open(URI, "window-padding-x");
+    let completions = server.complete(URI, 0, 16);
+    assert!(completions.is_array());
+    assert_eq!(labels(&completions), ["window-padding-x"]);
+}
+
+#[test]
+fn range_formatting_edits_only_the_block_in_range() {
+    let mut server = Server::start(json!({}));
+    server.open(URI, "a=1\n\nb=2\nlong-key=3\n");
+    let edits = server.request(
+        "textDocument/rangeFormatting",
+        json!({
+            "textDocument": { "uri": URI },
+            "range": { "start": { "line": 2, "character": 0 }, "end": { "line": 2, "character": 3 } },
+            "options": { "tabSize": 4, "insertSpaces": true }
+        }),
+    );
+    let edits = edits.as_array().unwrap();
+    assert_eq!(edits.len(), 1);
+    assert_eq!(edits[0]["range"]["start"]["line"], 2);
+    assert_eq!(edits[0]["newText"], "b        = 2\nlong-key = 3");
+}
+
+#[test]
+fn each_document_keeps_its_own_text() {
+    let mut server = Server::start(json!({}));
+    server.open(URI, "font");
+    server.open(OTHER_URI, "cursor-sty");
+    server.change(URI, 2, "window-padding-");
+    assert!(labels(&server.complete(URI, 0, 15)).contains(&"window-padding-x".to_string()));
+    assert!(labels(&server.complete(OTHER_URI, 0, 10)).contains(&"cursor-style".to_string()));
+}
+
+#[test]
+fn completion_before_did_open_reads_the_file() {
+    let dir = temp_dir("unopened");
+    let path = dir.join("config");
+    std::fs::write(&path, "cursor-style = \n").unwrap();
+    let uri = format!("file://{}", path.display());
+
+    let mut server = Server::start(json!({}));
+    let completions = server.complete(&uri, 0, 15);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(labels(&completions).contains(&"block".to_string()));
+}
+
+#[test]
+fn custom_themes_come_from_ghostty_config_dir() {
+    let dir = temp_dir("config-dir");
+    std::fs::create_dir_all(dir.join("themes")).unwrap();
+    std::fs::write(
+        dir.join("themes").join("My Theme"),
+        "background = #000000\n",
+    )
+    .unwrap();
+
+    let mut server = Server::start_with_env(json!({}), &[("GHOSTTY_CONFIG_DIR", &dir)]);
+    server.open(URI, "theme = my");
+    let completions = server.complete(URI, 0, 10);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(labels(&completions).contains(&"My Theme".to_string()));
+}
+
+#[test]
+fn changed_include_revalidates_the_including_document() {
+    let dir = temp_dir("includes");
+    let config = dir.join("config");
+    let include = dir.join("extra.conf");
+    let uri = format!("file://{}", config.display());
+
+    let mut server = Server::start(json!({ "checkPaths": true }));
+    server.open(&uri, "config-file = extra.conf\n");
+    assert_eq!(server.diagnostics(&uri).len(), 1);
+
+    std::fs::write(&include, "font-size = 14\n").unwrap();
+    server.notify(
+        "workspace/didChangeWatchedFiles",
+        json!({ "changes": [{ "uri": format!("file://{}", include.display()), "type": 1 }] }),
+    );
+    let diagnostics = server.diagnostics(&uri);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(diagnostics, Vec::<Value>::new());
+}
+
+#[test]
+fn version_flag_prints_versions_and_exits() {
+    let output = Command::new(env!("CARGO_BIN_EXE_ghostty-lsp"))
+        .arg("--version")
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let mut lines = stdout.lines();
+    assert_eq!(
+        lines.next(),
+        Some(concat!("ghostty-lsp ", env!("CARGO_PKG_VERSION")))
+    );
+    let schema = lines.next().unwrap();
+    assert!(schema.starts_with("schema "), "{}", schema);
+    assert!(schema.ends_with(" options"), "{}", schema);
+}
+
+#[test]
+fn validate_text_command_lints_its_argument() {
+    let mut server = Server::start(json!({}));
+    let diagnostics = server.request(
+        "workspace/executeCommand",
+        json!({ "command": "ghostty.validateText", "arguments": ["font-size = 14\nfont-sise = 14\n"] }),
+    );
+    let diagnostics = diagnostics.as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["range"]["start"]["line"], 1);
+    assert_eq!(diagnostics[0]["code"], "unknown-key");
+
+    server.send(json!({
+        "jsonrpc": "2.0",
+        "id": 100,
+        "method": "workspace/executeCommand",
+        "params": { "command": "ghostty.validateText", "arguments": [] }
+    }));
+    let response = server.wait_for(|message| message["id"] == 100);
+    assert_eq!(response["error"]["code"], -32602);
+}
+
+#[test]
+fn shutdown_clears_diagnostics_and_exit_succeeds() {
+    let mut server = Server::start(json!({}));
+    server.open(URI, "font-sise = 14\n");
+    assert_eq!(server.diagnostics(URI).len(), 1);
+
+    // The response and the cleared diagnostics can arrive in either order
+    server.send(json!({ "jsonrpc": "2.0", "id": 100, "method": "shutdown" }));
+    let mut cleared = None;
+    let mut responded = false;
+    while cleared.is_none() || !responded {
+        let message = server.wait_for(|message| {
+            message["id"] == 100 || message["method"] == "textDocument/publishDiagnostics"
+        });
+        if message["id"] == 100 {
+            responded = true;
+        } else {
+            cleared = Some(message["params"]["diagnostics"].clone());
+        }
+    }
+    assert_eq!(cleared, Some(json!([])));
+    assert_eq!(server.exit(), Some(0));
+}
+
+#[test]
+fn exit_without_shutdown_fails() {
+    let mut server = Server::start(json!({}));
+    assert_eq!(server.exit(), Some(1));
+}
+
+#[test]
+fn diagnostics_summary_counts_every_document() {
+    let mut server = Server::start(json!({ "diagnosticsSummary": true }));
+    server.open(URI, "font-sise = 14\n");
+    server.open(OTHER_URI, "cursor-style = round\n");
+    let summary = server.wait_for(|message| {
+        message["method"] == "$/ghostty/diagnosticsSummary" && message["params"]["documents"] == 2
+    });
+    assert_eq!(
+        summary["params"],
+        json!({ "errors": 0, "warnings": 2, "information": 0, "documents": 2 })
+    );
+}
+
+#[test]
+fn minimal_completion_detail_leaves_out_documentation() {
+    let mut server = Server::start(json!({ "completionDetail": "minimal" }));
+    server.open(URI, "window-padding-x");
+    let completions = server.complete(URI, 0, 16);
+    let item = &completions[0];
+    assert_eq!(item["label"], "window-padding-x");
+    assert!(item.get("detail").is_none() && item.get("documentation").is_none());
+
+    let resolved = server.request("completionItem/resolve", item.clone());
+    assert!(resolved["documentation"]["value"].is_string());
+}
+
+#[test]
+fn interleaved_requests_for_several_documents() {
+    let documents = [
+        (
+            "file:///tmp/ghostty-lsp-tests/a",
+            "font-fam",
+            8,
+            "font-family",
+        ),
+        (
+            "file:///tmp/ghostty-lsp-tests/b",
+            "cursor-sty",
+            10,
+            "cursor-style",
+        ),
+        (
+            "file:///tmp/ghostty-lsp-tests/c",
+            "window-padding-",
+            15,
+            "window-padding-x",
+        ),
+        (
+            "file:///tmp/ghostty-lsp-tests/d",
+            "mouse-hide",
+            10,
+            "mouse-hide-while-typing",
+        ),
+    ];
+    let mut server = Server::start(json!({}));
+    for (uri, text, _, _) in documents {
+        server.open(uri, text);
+    }
+
+    // Every request goes out before any response is read
+    for round in 0..3 {
+        for (i, (uri, _, character, _)) in documents.iter().enumerate() {
+            server.send(json!({
+                "jsonrpc": "2.0",
+                "id": 100 + round * documents.len() + i,
+                "method": "textDocument/completion",
+                "params": {
+                    "textDocument": { "uri": uri },
+                    "position": { "line": 0, "character": character }
+                }
+            }));
+        }
+    }
+
+    // Responses may come back in any order
+    let mut responses = std::collections::HashMap::new();
+    while responses.len() < 3 * documents.len() {
+        let response = server.wait_for(|message| {
+            message.get("method").is_none() && message["id"].as_u64() >= Some(100)
+        });
+        responses.insert(response["id"].as_u64().unwrap() as usize, response);
+    }
+    for (id, response) in responses {
+        let (uri, _, _, expected) = documents[(id - 100) % documents.len()];
+        assert!(
+            labels(&response["result"]).contains(&expected.to_string()),
+            "{} didn't offer {}",
+            uri,
+            expected
+        );
+    }
+}
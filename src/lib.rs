@@ -1,4 +1,33 @@
-use zed_extension_api::{self as zed, LanguageServerId, Result};
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+use zed_extension_api::{self as zed, serde_json, LanguageServerId, Result};
+
+const CONFIG_DIR_ENV: &str = "GHOSTTY_LSP_CONFIG_DIR";
+
+/// Set to any value to look for a newer release even if the cache is fresh.
+const UPDATE_ENV: &str = "GHOSTTY_LSP_UPDATE";
+
+/// Directory to download the binary and its release cache into, instead of
+/// the extension's working directory.
+const INSTALL_DIR_ENV: &str = "GHOSTTY_LSP_INSTALL_DIR";
+
+/// Set to any value to prefer a `ghostty-lsp` built in the worktree's
+/// `target` directory, for working on the server itself.
+const DEV_ENV: &str = "GHOSTTY_LSP_DEV";
+
+/// Cargo profiles checked for a development build, in order.
+const DEV_PROFILES: &[&str] = &["release", "debug"];
+
+const RELEASE_CACHE_FILE: &str = "ghostty-lsp-release.json";
+const RELEASE_CACHE_MAX_AGE_SECS: u64 = 24 * 60 * 60;
+
+/// Worktree-relative locations of a Ghostty config, and the directory each one
+/// lives in.
+const WORKTREE_CONFIGS: &[(&str, &str)] = &[
+    ("config", ""),
+    ("ghostty/config", "ghostty"),
+    (".config/ghostty/config", ".config/ghostty"),
+];
 
 struct GhosttyExtension {
     cached_binary_path: Option<String>,
@@ -16,14 +45,38 @@ impl zed::Extension for GhosttyExtension {
         _language_server_id: &LanguageServerId,
         worktree: &zed::Worktree,
     ) -> Result<zed::Command> {
-        let env = worktree.shell_env();
+        let mut env = worktree.shell_env();
+        let is_set = |name: &str| env.iter().any(|(k, v)| k == name && !v.is_empty());
 
         // Check for custom path: set GHOSTTY_LSP_PATH=/path/to/ghostty-lsp in your shell
-        let binary_path = env
-            .iter()
-            .find(|(k, _)| k == "GHOSTTY_LSP_PATH")
-            .map(|(_, v)| v.clone())
-            .unwrap_or_else(|| self.get_or_download_lsp_binary());
+        let custom_path = env.iter().find(|(k, _)| k == "GHOSTTY_LSP_PATH");
+        let dev_binary = is_set(DEV_ENV).then(|| dev_binary(worktree)).flatten();
+        let binary_path = match (custom_path, dev_binary) {
+            (Some((_, path)), _) => {
+                eprintln!("ghostty-lsp: using GHOSTTY_LSP_PATH {}", path);
+                path.clone()
+            }
+            (None, Some(path)) => {
+                eprintln!("ghostty-lsp: using development build {}", path);
+                path
+            }
+            (None, None) => {
+                let force_update = is_set(UPDATE_ENV);
+                let install_dir = env
+                    .iter()
+                    .find(|(k, v)| k == INSTALL_DIR_ENV && !v.is_empty())
+                    .map(|(_, v)| v.trim_end_matches('/').to_string());
+                let path = self.get_or_download_lsp_binary(install_dir.as_deref(), force_update)?;
+                eprintln!("ghostty-lsp: using downloaded binary {}", path);
+                path
+            }
+        };
+
+        // Tell the LSP where this worktree's Ghostty config lives, so workspace
+        // discovery can find it even outside the worktree's own folders
+        if let Some(config_dir) = detect_config_dir(worktree, &env) {
+            env.push((CONFIG_DIR_ENV.to_string(), config_dir));
+        }
 
         Ok(zed::Command {
             command: binary_path,
@@ -34,32 +87,39 @@ impl zed::Extension for GhosttyExtension {
 }
 
 impl GhosttyExtension {
-    fn get_or_download_lsp_binary(&mut self) -> String {
+    fn get_or_download_lsp_binary(
+        &mut self,
+        install_dir: Option<&str>,
+        force_update: bool,
+    ) -> Result<String> {
         if let Some(path) = &self.cached_binary_path {
-            return path.clone();
+            return Ok(path.clone());
         }
 
         let (os, arch) = zed::current_platform();
-
-        let binary_name = match os {
-            zed::Os::Mac | zed::Os::Linux => "ghostty-lsp",
-            zed::Os::Windows => "ghostty-lsp.exe",
-        };
+        let install = Install::new(install_dir, binary_name(os))?;
 
         // Try to download from GitHub releases
-        if let Ok(()) = self.try_download_binary(binary_name, os, arch) {
-            self.cached_binary_path = Some(binary_name.to_string());
+        match self.try_download_binary(&install, os, arch, force_update) {
+            Ok(()) => {
+                self.cached_binary_path = Some(install.binary_path.clone());
+                Ok(install.binary_path)
+            }
+            // A binary left by an earlier download still works
+            Err(_) if fs::metadata(&install.binary_path).is_ok() => Ok(install.binary_path),
+            Err(e) => Err(format!(
+                "ghostty-lsp: {}; set GHOSTTY_LSP_PATH to a local build",
+                e
+            )),
         }
-
-        // Return binary name regardless - if download failed but binary exists locally, it will work
-        binary_name.to_string()
     }
 
     fn try_download_binary(
         &self,
-        binary_name: &str,
+        install: &Install,
         os: zed::Os,
         arch: zed::Architecture,
+        force_update: bool,
     ) -> std::result::Result<(), String> {
         let os_name = match os {
             zed::Os::Mac => "darwin",
@@ -70,37 +130,249 @@ impl GhosttyExtension {
         let arch_name = match arch {
             zed::Architecture::Aarch64 => "aarch64",
             zed::Architecture::X8664 => "x86_64",
-            _ => return Err("Unsupported architecture".to_string()),
+            zed::Architecture::X86 => {
+                return Err("no prebuilt binary for your platform (x86)".to_string())
+            }
         };
 
         let asset_name = format!("ghostty-lsp-{}-{}.tar.gz", os_name, arch_name);
 
-        let release = zed::latest_github_release(
+        let binary_name = install.binary_path.as_str();
+        let cached =
+            CachedRelease::load(&install.cache_path).filter(|c| c.asset_name == asset_name);
+        let installed = fs::metadata(binary_name).is_ok();
+        if installed && !force_update && cached.as_ref().is_some_and(CachedRelease::is_fresh) {
+            return Ok(());
+        }
+
+        let release = match zed::latest_github_release(
             "Else00/ghostty-zed-extension",
             zed::GithubReleaseOptions {
                 require_assets: true,
                 pre_release: false,
             },
-        )
-        .map_err(|e| e.to_string())?;
+        ) {
+            Ok(release) => release,
+            // Offline or rate limited: keep whatever release was installed last
+            Err(_) if installed && cached.is_some() => return Ok(()),
+            Err(e) => match cached {
+                Some(cached) => return download_binary(&cached.download_url, binary_name, os),
+                None => return Err(e),
+            },
+        };
+
+        if let Some(cached) = cached.filter(|c| installed && c.version == release.version) {
+            CachedRelease {
+                fetched_at: now_secs(),
+                ..cached
+            }
+            .save(&install.cache_path);
+            return Ok(());
+        }
 
         let asset = release
             .assets
             .iter()
             .find(|a| a.name == asset_name)
-            .ok_or_else(|| format!("No asset found for {}", asset_name))?;
+            .ok_or_else(|| {
+                format!(
+                    "no prebuilt binary for your platform ({}-{}) in release {}",
+                    os_name, arch_name, release.version
+                )
+            })?;
+
+        download_binary(&asset.download_url, binary_name, os)?;
+
+        CachedRelease {
+            version: release.version.clone(),
+            asset_name,
+            download_url: asset.download_url.clone(),
+            fetched_at: now_secs(),
+        }
+        .save(&install.cache_path);
+
+        Ok(())
+    }
+}
+
+/// A `ghostty-lsp` built with cargo in the worktree. The worktree API only
+/// reads text, so the build is detected by the dep-info file cargo writes
+/// next to the binary.
+fn dev_binary(worktree: &zed::Worktree) -> Option<String> {
+    let profile = DEV_PROFILES.iter().find(|profile| {
+        worktree
+            .read_text_file(&format!("target/{}/ghostty-lsp.d", profile))
+            .is_ok()
+    })?;
+    Some(format!(
+        "{}/target/{}/{}",
+        worktree.root_path(),
+        profile,
+        binary_name(zed::current_platform().0)
+    ))
+}
 
-        zed::download_file(
-            &asset.download_url,
-            binary_name,
-            zed::DownloadedFileType::GzipTar,
-        )
+fn binary_name(os: zed::Os) -> &'static str {
+    match os {
+        zed::Os::Mac | zed::Os::Linux => "ghostty-lsp",
+        zed::Os::Windows => "ghostty-lsp.exe",
+    }
+}
+
+/// Where the binary and its release cache live: the extension's working
+/// directory, or `GHOSTTY_LSP_INSTALL_DIR` within it when set. Zed only lets
+/// extensions write inside their working directory, so the setting can't
+/// point anywhere else.
+struct Install {
+    binary_path: String,
+    cache_path: String,
+}
+
+impl Install {
+    fn new(dir: Option<&str>, binary_name: &str) -> Result<Self> {
+        let Some(dir) = dir else {
+            return Ok(Self {
+                binary_path: binary_name.to_string(),
+                cache_path: RELEASE_CACHE_FILE.to_string(),
+            });
+        };
+        let outside = dir.starts_with('/')
+            || dir.contains(':')
+            || dir.split(['/', '\\']).any(|part| part == "..");
+        if outside {
+            return Err(format!(
+                "ghostty-lsp: {} {} must be a relative path inside the extension's working directory",
+                INSTALL_DIR_ENV, dir
+            ));
+        }
+        fs::create_dir_all(dir).map_err(|e| {
+            format!(
+                "ghostty-lsp: can't create {} {}: {}",
+                INSTALL_DIR_ENV, dir, e
+            )
+        })?;
+        Ok(Self {
+            binary_path: format!("{}/{}", dir, binary_name),
+            cache_path: format!("{}/{}", dir, RELEASE_CACHE_FILE),
+        })
+    }
+}
+
+fn download_binary(url: &str, binary_name: &str, os: zed::Os) -> std::result::Result<(), String> {
+    zed::download_file(url, binary_name, zed::DownloadedFileType::GzipTar)
         .map_err(|e| e.to_string())?;
+    if let Err(e) = check_executable_format(binary_name, os) {
+        // Don't leave it behind to be picked up as a working earlier download
+        let _ = fs::remove_file(binary_name);
+        return Err(e);
+    }
+    zed::make_file_executable(binary_name).map_err(|e| e.to_string())
+}
 
-        zed::make_file_executable(binary_name).map_err(|e| e.to_string())?;
+/// Checks the file starts with the executable magic for `os`, catching
+/// truncated downloads and HTML error pages saved in place of the binary.
+fn check_executable_format(binary_name: &str, os: zed::Os) -> std::result::Result<(), String> {
+    let mut magic = [0u8; 4];
+    let read = fs::File::open(binary_name)
+        .and_then(|mut file| std::io::Read::read(&mut file, &mut magic))
+        .map_err(|e| format!("can't read downloaded binary: {}", e))?;
+    let magic = &magic[..read];
 
+    let (expected, valid) = match os {
+        zed::Os::Linux => ("ELF", magic == b"\x7fELF"),
+        zed::Os::Mac => (
+            "Mach-O",
+            matches!(
+                magic,
+                [0xfe, 0xed, 0xfa, 0xce | 0xcf]
+                    | [0xce | 0xcf, 0xfa, 0xed, 0xfe]
+                    | [0xca, 0xfe, 0xba, 0xbe]
+            ),
+        ),
+        zed::Os::Windows => ("PE", magic.starts_with(b"MZ")),
+    };
+    if valid {
         Ok(())
+    } else {
+        Err(format!(
+            "downloaded binary isn't a valid {} executable (starts with {:02x?})",
+            expected, magic
+        ))
+    }
+}
+
+/// The release the downloaded binary came from, cached next to it so GitHub is
+/// queried at most once a day.
+struct CachedRelease {
+    version: String,
+    asset_name: String,
+    download_url: String,
+    fetched_at: u64,
+}
+
+impl CachedRelease {
+    fn load(path: &str) -> Option<Self> {
+        let text = fs::read_to_string(path).ok()?;
+        let value: serde_json::Value = serde_json::from_str(&text).ok()?;
+        let field = |name: &str| value.get(name)?.as_str().map(String::from);
+        Some(Self {
+            version: field("version")?,
+            asset_name: field("assetName")?,
+            download_url: field("downloadUrl")?,
+            fetched_at: value.get("fetchedAt")?.as_u64()?,
+        })
+    }
+
+    fn save(&self, path: &str) {
+        let value = serde_json::json!({
+            "version": self.version,
+            "assetName": self.asset_name,
+            "downloadUrl": self.download_url,
+            "fetchedAt": self.fetched_at,
+        });
+        // A missing cache only costs an extra lookup next time
+        let _ = fs::write(path, value.to_string());
+    }
+
+    fn is_fresh(&self) -> bool {
+        now_secs().saturating_sub(self.fetched_at) < RELEASE_CACHE_MAX_AGE_SECS
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Returns the Ghostty config directory for `worktree`: the worktree itself when
+/// it contains a config, otherwise the user's default config directory.
+fn detect_config_dir(worktree: &zed::Worktree, env: &[(String, String)]) -> Option<String> {
+    let root = worktree.root_path();
+    let root_is_config_dir = root.ends_with("ghostty") || root.ends_with("com.mitchellh.ghostty");
+    for (file, dir) in WORKTREE_CONFIGS {
+        if dir.is_empty() && !root_is_config_dir {
+            continue;
+        }
+        if worktree.read_text_file(file).is_ok() {
+            return Some(if dir.is_empty() {
+                root
+            } else {
+                format!("{}/{}", root, dir)
+            });
+        }
     }
+
+    let var = |name: &str| {
+        env.iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.clone())
+            .filter(|v| !v.is_empty())
+    };
+    var("XDG_CONFIG_HOME")
+        .map(|xdg| format!("{}/ghostty", xdg))
+        .or_else(|| var("HOME").map(|home| format!("{}/.config/ghostty", home)))
 }
 
 zed::register_extension!(GhosttyExtension);